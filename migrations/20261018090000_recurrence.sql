@@ -0,0 +1,2 @@
+-- Repeat rule of a todo: daily, weekly:mon,fri, monthly:15 or after:3
+ALTER TABLE todo ADD COLUMN recurrence TEXT;
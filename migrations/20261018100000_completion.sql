@@ -0,0 +1,4 @@
+-- When a todo was completed, and whether it has been archived out of the active lists.
+-- Todos completed before this migration have no completion date.
+ALTER TABLE todo ADD COLUMN date_completed TEXT;
+ALTER TABLE todo ADD COLUMN archived BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,2 @@
+-- Deleted todos stay in the trash until restored or purged
+ALTER TABLE todo ADD COLUMN deleted_at TEXT;
@@ -0,0 +1,8 @@
+-- One row per undoable action, with the affected rows before and after it as JSON
+CREATE TABLE undo_log (
+  entry_id INTEGER PRIMARY KEY AUTOINCREMENT,
+  description TEXT NOT NULL,
+  changes TEXT NOT NULL,
+  date_created TEXT NOT NULL,
+  undone BOOLEAN NOT NULL DEFAULT FALSE
+);
@@ -0,0 +1,11 @@
+-- Every change to a field of a todo. Not tied to the todo table so the history outlives purged todos.
+CREATE TABLE todo_history (
+  history_id INTEGER PRIMARY KEY AUTOINCREMENT,
+  todo_id INTEGER NOT NULL,
+  field TEXT NOT NULL,
+  old_value TEXT,
+  new_value TEXT,
+  date_changed TEXT NOT NULL,
+  source TEXT NOT NULL
+);
+CREATE INDEX todo_history_todo ON todo_history (todo_id);
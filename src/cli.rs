@@ -0,0 +1,395 @@
+use std::error::Error;
+
+use crate::config::Config;
+use crate::model::db::{DaylistOptions, Db, ProjectFilter, SmartView};
+use crate::model::schema::{Project, Todo};
+use crate::model::recurrence::Recurrence;
+use crate::model::date_input::{parse_date, DATE_INPUT_HELP};
+use crate::reminder::{ReminderError, Reminders, SystemClock};
+use crate::reminder::notify::{CommandNotifier, DesktopNotifier, StdoutNotifier};
+
+pub const USAGE: &str = "Usage: day_list [COMMAND]
+
+Without a command the TUI is started.
+
+Commands:
+  add <title> [--due DATE] [--reminder DATE] [--priority 1-9] [--project NAME]
+              [--description TEXT] [--parent ID] [--repeat RULE]
+  list [--json] [--project NAME | --inbox | --view overdue|today|week|undated]
+  done <id>
+  rm <id>                move to the trash, subtasks included
+  restore <id>
+  trash [--json]
+  undo                   undo the last change, from the CLI or the TUI
+  redo
+  search <query> [--json]
+  archive [--days N]     archive todos completed more than N days ago (archive_after_days)
+  daemon [--notifier stdout|command|desktop]
+  help
+
+Repeat rules: daily, weekly:mon,fri, monthly:15, after:3 (days after completion)
+Dates: tomorrow 9am, next fri, in 3 days, eod, 2026-11-02 14:00 or the configured input format
+";
+
+// Run a non-interactive subcommand, `args` excludes the program name
+pub async fn run(args: &[String], list_db: &Db, config: &Config) -> Result<(), Box<dyn Error>> {
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => return Err(USAGE.into()),
+    };
+
+    match command {
+        "add" => add(rest, list_db, config).await,
+        "list" | "ls" => list(rest, list_db, config).await,
+        "done" => done(rest, list_db).await,
+        "rm" => rm(rest, list_db).await,
+        "restore" => restore(rest, list_db).await,
+        "trash" => trash(rest, list_db, config).await,
+        "undo" => undo(rest, list_db, false).await,
+        "redo" => undo(rest, list_db, true).await,
+        "search" => search(rest, list_db, config).await,
+        "archive" => archive(rest, list_db, config).await,
+        "daemon" => daemon(rest, list_db, config).await,
+        "help" | "--help" | "-h" => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(format!("unknown command '{}'\n\n{}", command, USAGE).into()),
+    }
+}
+
+// Empty the trash of anything older than the retention period, a period reaching back past
+// the earliest date chrono knows means nothing is old enough. Done when the TUI or the daemon
+// starts, not for every command.
+pub async fn purge_expired_trash(list_db: &Db, config: &Config) {
+    let cutoff = chrono::Local::now().naive_local()
+        .checked_sub_signed(chrono::Duration::days(config.trash_retention_days.into()));
+    if let Some(cutoff) = cutoff {
+        if let Err(e) = list_db.purge_trash(cutoff).await {
+            eprintln!("day_list: could not empty the trash: {}", e);
+        }
+    }
+}
+
+// Positional arguments and --flag value pairs. Flags in `switches` take no value.
+struct Args {
+    positional: Vec<String>,
+    flags: Vec<(String, Option<String>)>,
+}
+
+impl Args {
+    fn parse(args: &[String], switches: &[&str]) -> Result<Args, Box<dyn Error>> {
+        let mut parsed = Args { positional: vec![], flags: vec![] };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.strip_prefix("--") {
+                Some(flag) if switches.contains(&flag) => parsed.flags.push((flag.to_string(), None)),
+                Some(flag) => match iter.next() {
+                    Some(value) => parsed.flags.push((flag.to_string(), Some(value.clone()))),
+                    None => return Err(format!("--{} needs a value", flag).into()),
+                },
+                None => parsed.positional.push(arg.clone()),
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn value(&self, flag: &str) -> Option<&str> {
+        self.flags.iter()
+            .rev() // last one wins
+            .find(|(name, _)| name == flag)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    fn switch(&self, flag: &str) -> bool {
+        self.flags.iter().any(|(name, _)| name == flag)
+    }
+
+    fn check(&self, known: &[&str]) -> Result<(), Box<dyn Error>> {
+        match self.flags.iter().find(|(name, _)| !known.contains(&name.as_str())) {
+            Some((name, _)) => Err(format!("unknown option --{}", name).into()),
+            None => Ok(()),
+        }
+    }
+
+    fn id(&self) -> Result<i64, Box<dyn Error>> {
+        match self.positional.as_slice() {
+            [id] => id.parse().map_err(|_| format!("'{}' is not a todo id", id).into()),
+            _ => Err("expected exactly one todo id".into()),
+        }
+    }
+}
+
+async fn add(args: &[String], list_db: &Db, config: &Config) -> Result<(), Box<dyn Error>> {
+    let args = Args::parse(args, &[])?;
+    args.check(&["due", "reminder", "priority", "project", "description", "parent", "repeat"])?;
+
+    let title = args.positional.join(" ");
+    if title.trim().is_empty() {
+        return Err("a todo needs a title".into());
+    }
+
+    let date_due = args.value("due").map(|due| date_arg(due, config)).transpose()?;
+    let reminder_date = args.value("reminder").map(|reminder| date_arg(reminder, config)).transpose()?;
+    let priority = match args.value("priority") {
+        Some(priority) => match priority.parse() {
+            Ok(priority @ 1..=9) => priority,
+            _ => return Err(format!("priority must be 1-9, got '{}'", priority).into()),
+        },
+        None => config.default_priority,
+    };
+    // Same as the edit form: an unknown project name creates the project, along with the todo
+    let projects = list_db.fetch_projects().await?;
+    let (project_id, new_project) = match args.value("project") {
+        Some(name) => match find_project(&projects, name) {
+            Some(project) => (Some(project.project_id), None),
+            None => (None, Some(name)),
+        },
+        None => (None, None),
+    };
+    let parent_todo = match args.value("parent") {
+        Some(id) => {
+            let id: i64 = id.parse().map_err(|_| format!("'{}' is not a todo id", id))?;
+            match list_db.fetch_todo(id).await? {
+                Some(todo) if todo.deleted_at.is_some() => return Err(format!("todo {} is in the trash, restore it first", id).into()),
+                Some(_) => Some(id),
+                None => return Err(format!("no todo with id {}", id).into()),
+            }
+        }
+        None => None,
+    };
+
+    let recurrence = args.value("repeat").map(|rule| rule.parse::<Recurrence>()).transpose()?;
+
+    let todo = Todo {
+        todo_id: 0,
+        title,
+        description: args.value("description").map(String::from),
+        date_created: None,
+        status: 0,
+        date_due,
+        reminder_date,
+        parent_todo,
+        priority,
+        project_id,
+        recurrence,
+        date_completed: None,
+        archived: false,
+        deleted_at: None,
+    };
+    let id = match new_project {
+        Some(name) => list_db.create_todo_in_new_project(&todo, name).await?,
+        None => list_db.create_todo(&todo).await?,
+    };
+    println!("{}", id);
+    Ok(())
+}
+
+async fn list(args: &[String], list_db: &Db, config: &Config) -> Result<(), Box<dyn Error>> {
+    let args = Args::parse(args, &["json", "inbox"])?;
+    args.check(&["json", "inbox", "project", "view"])?;
+
+    if let Some(name) = args.value("view") {
+        if args.value("project").is_some() || args.switch("inbox") {
+            return Err("--view can't be combined with --project or --inbox".into());
+        }
+        let view = SmartView::ALL.into_iter()
+            .find(|view| view_arg(view) == name)
+            .ok_or_else(|| format!("unknown view '{}', expected overdue, today, week or undated", name))?;
+        let todos = list_db.fetch_smart_view(view, chrono::Local::now().date_naive(), 0, u32::MAX).await?;
+        return print_todos(&todos, args.switch("json"), list_db, config).await;
+    }
+
+    let filter = match (args.value("project"), args.switch("inbox")) {
+        (Some(_), true) => return Err("--project and --inbox can't be combined".into()),
+        (Some(name), false) => {
+            let projects = list_db.fetch_projects().await?;
+            match find_project(&projects, name) {
+                Some(project) => ProjectFilter::Project(project.project_id),
+                None => return Err(format!("no project named '{}'", name).into()),
+            }
+        }
+        (None, true) => ProjectFilter::Inbox,
+        (None, false) => ProjectFilter::All,
+    };
+
+    let todos = list_db.fetch_todos(&filter, &DaylistOptions::default(), 0, u32::MAX).await?;
+    print_todos(&todos, args.switch("json"), list_db, config).await
+}
+
+// How a smart view is named on the command line
+fn view_arg(view: &SmartView) -> &'static str {
+    match view {
+        SmartView::Overdue => "overdue",
+        SmartView::Today => "today",
+        SmartView::Next7Days => "week",
+        SmartView::NoDueDate => "undated",
+    }
+}
+
+async fn done(args: &[String], list_db: &Db) -> Result<(), Box<dyn Error>> {
+    let args = Args::parse(args, &[])?;
+    args.check(&[])?;
+    let id = args.id()?;
+
+    match list_db.fetch_todo(id).await? {
+        Some(todo) if todo.deleted_at.is_some() => Err(format!("todo {} is in the trash, restore it first", id).into()),
+        Some(_) => Ok(list_db.complete_todo(id).await?),
+        None => Err(format!("no todo with id {}", id).into()),
+    }
+}
+
+async fn rm(args: &[String], list_db: &Db) -> Result<(), Box<dyn Error>> {
+    let args = Args::parse(args, &[])?;
+    args.check(&[])?;
+    let id = args.id()?;
+
+    list_db.fetch_todo(id).await?.ok_or(format!("no todo with id {}", id))?;
+    list_db.delete_todo(id).await?;
+    Ok(())
+}
+
+async fn restore(args: &[String], list_db: &Db) -> Result<(), Box<dyn Error>> {
+    let args = Args::parse(args, &[])?;
+    args.check(&[])?;
+    let id = args.id()?;
+
+    match list_db.fetch_todo(id).await? {
+        Some(todo) if todo.deleted_at.is_some() => Ok(list_db.restore_todo(id).await?),
+        Some(_) => Err(format!("todo {} is not in the trash", id).into()),
+        None => Err(format!("no todo with id {}", id).into()),
+    }
+}
+
+async fn trash(args: &[String], list_db: &Db, config: &Config) -> Result<(), Box<dyn Error>> {
+    let args = Args::parse(args, &["json"])?;
+    args.check(&["json"])?;
+
+    let todos = list_db.fetch_trash(0, u32::MAX).await?;
+    print_todos(&todos, args.switch("json"), list_db, config).await
+}
+
+async fn undo(args: &[String], list_db: &Db, redo: bool) -> Result<(), Box<dyn Error>> {
+    let args = Args::parse(args, &[])?;
+    args.check(&[])?;
+    if !args.positional.is_empty() {
+        return Err("undo and redo take no arguments".into());
+    }
+
+    let (done, verb) = if redo {
+        (list_db.redo().await?, "Redid")
+    } else {
+        (list_db.undo().await?, "Undid")
+    };
+    match done {
+        Some(description) => println!("{}: {}", verb, description),
+        None => println!("Nothing to {}", if redo { "redo" } else { "undo" }),
+    }
+    Ok(())
+}
+
+async fn search(args: &[String], list_db: &Db, config: &Config) -> Result<(), Box<dyn Error>> {
+    let args = Args::parse(args, &["json"])?;
+    args.check(&["json"])?;
+
+    let query = args.positional.join(" ");
+    if query.is_empty() {
+        return Err("nothing to search for".into());
+    }
+
+    let todos = list_db.search(&query, 0, u32::MAX).await?;
+    print_todos(&todos, args.switch("json"), list_db, config).await
+}
+
+async fn archive(args: &[String], list_db: &Db, config: &Config) -> Result<(), Box<dyn Error>> {
+    let args = Args::parse(args, &[])?;
+    args.check(&["days"])?;
+
+    let days: u32 = match args.value("days") {
+        Some(days) => days.parse().map_err(|_| format!("--days needs a number, got '{}'", days))?,
+        None => config.archive_after_days,
+    };
+    let cutoff = chrono::Local::now().naive_local()
+        .checked_sub_signed(chrono::Duration::days(days.into()))
+        .ok_or_else(|| format!("--days {} reaches back too far", days))?;
+    let archived = list_db.archive_completed(cutoff).await?;
+    println!("Archived {} todos", archived);
+    Ok(())
+}
+
+// Keep running and send reminders as they come due, until killed
+async fn daemon(args: &[String], list_db: &Db, config: &Config) -> Result<(), Box<dyn Error>> {
+    let args = Args::parse(args, &[])?;
+    args.check(&["notifier"])?;
+
+    // Without a choice: the configured command, then the desktop, then stdout
+    let notifier = match args.value("notifier") {
+        Some(notifier) => notifier,
+        None if config.notify_command.is_some() => "command",
+        None if DesktopNotifier::available() => "desktop",
+        None => "stdout",
+    };
+
+    if !["stdout", "command", "desktop"].contains(&notifier) {
+        return Err(format!("unknown notifier '{}', expected stdout, command or desktop", notifier).into());
+    }
+    purge_expired_trash(list_db, config).await;
+
+    let list_db = list_db.clone();
+    let report = |e: ReminderError| eprintln!("day_list: {}", e);
+    match notifier {
+        "stdout" => Reminders::new(list_db, SystemClock, StdoutNotifier).run(report).await,
+        "command" => {
+            let command = config.notify_command.clone().ok_or("notify_command is not set in the config file")?;
+            Reminders::new(list_db, SystemClock, CommandNotifier { command }).run(report).await
+        }
+        "desktop" => {
+            if !DesktopNotifier::available() {
+                return Err("no D-Bus session to send desktop notifications to".into());
+            }
+            Reminders::new(list_db, SystemClock, DesktopNotifier).run(report).await
+        }
+        _ => unreachable!("notifier checked above"),
+    }
+    Ok(())
+}
+
+// One line per todo, or a JSON array for other programs
+async fn print_todos(todos: &[Todo], json: bool, list_db: &Db, config: &Config) -> Result<(), Box<dyn Error>> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(todos)?);
+        return Ok(());
+    }
+
+    let projects = list_db.fetch_projects().await?;
+    for todo in todos {
+        let status = if todo.status == 1 { "[x]" } else { "[ ]" };
+        let mut line = format!("{:>4} {} {} (p{})", todo.todo_id, status, todo.title, todo.priority);
+        if let Some(due) = todo.date_due {
+            line.push_str(&format!(" due {}", due.format(&config.date_format)));
+        }
+        if let Some(rule) = &todo.recurrence {
+            line.push_str(&format!(" ↻ {}", rule));
+        }
+        if let Some(project) = projects.iter().find(|project| Some(project.project_id) == todo.project_id) {
+            line.push_str(&format!(" #{}", project.name));
+        }
+        if todo.archived {
+            line.push_str(" (archived)");
+        }
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+fn date_arg(input: &str, config: &Config) -> Result<chrono::NaiveDateTime, Box<dyn Error>> {
+    match parse_date(input, &config.input_date_format, chrono::Local::now().naive_local()) {
+        Ok(Some(date)) => Ok(date),
+        Ok(None) => Err("empty date".into()),
+        Err(e) => Err(format!("can't read '{}' as a date: {} ({})", input, e, DATE_INPUT_HELP).into()),
+    }
+}
+
+fn find_project<'a>(projects: &'a [Project], name: &str) -> Option<&'a Project> {
+    projects.iter().find(|project| project.name.eq_ignore_ascii_case(name))
+}
@@ -0,0 +1,222 @@
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use dotenv::dotenv;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteConnectOptions;
+
+use crate::model::db::DaylistOptions;
+
+pub const DEFAULT_DATE_FORMAT: &str = "%d/%m/%Y %H:%M:%S";
+pub const DEFAULT_INPUT_DATE_FORMAT: &str = "%d/%m/%y %H:%M";
+
+// Settings read from $XDG_CONFIG_HOME/day_list/config.toml, every key is optional
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub database_path: Option<PathBuf>, // defaults to $XDG_DATA_HOME/day_list/day_list.db
+    pub default_priority: i64,
+    pub date_format: String, // how dates are displayed
+    pub input_date_format: String, // how dates are typed in the edit form
+    pub page_size: u32,
+    pub bell: bool, // ring the terminal bell when a reminder goes off
+    pub notify_command: Option<Vec<String>>, // program and arguments `day_list daemon` runs for each reminder
+    pub archive_after_days: u32, // completed todos older than this are archived by `A` or `day_list archive`
+    pub trash_retention_days: u32, // trashed todos are purged for good after this long
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            database_path: None,
+            default_priority: 4,
+            date_format: String::from(DEFAULT_DATE_FORMAT),
+            input_date_format: String::from(DEFAULT_INPUT_DATE_FORMAT),
+            page_size: 10,
+            bell: false,
+            notify_command: None,
+            archive_after_days: 7,
+            trash_retention_days: 30,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Invalid(PathBuf, String), // parsed, but a value can't be used
+    NoHome(&'static str), // neither the XDG variable nor HOME says where the files go
+    DatabaseUrl(sqlx::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "could not read {}: {}", path.display(), e),
+            ConfigError::Parse(path, e) => write!(f, "invalid config in {}: {}", path.display(), e),
+            ConfigError::Invalid(path, e) => write!(f, "invalid config in {}: {}", path.display(), e),
+            ConfigError::NoHome(var) => write!(f, "neither ${} nor $HOME is set", var),
+            ConfigError::DatabaseUrl(e) => write!(f, "invalid DATABASE_URL: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    // A missing config file just means the defaults
+    pub fn load() -> Result<Config, ConfigError> {
+        let path = config_dir()?.join("config.toml");
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(ConfigError::Io(path, e)),
+        };
+
+        let config: Config = toml::from_str(&contents).map_err(|e| ConfigError::Parse(path.clone(), e))?;
+        config.validate().map_err(|e| ConfigError::Invalid(path, e))?;
+        Ok(config)
+    }
+
+    // Values that parse but would break the app later on
+    fn validate(&self) -> Result<(), String> {
+        if !(1..=9).contains(&self.default_priority) {
+            return Err(format!("default_priority must be 1-9, got {}", self.default_priority));
+        }
+        if self.page_size == 0 {
+            return Err(String::from("page_size must be at least 1"));
+        }
+        for (key, format) in [("date_format", &self.date_format), ("input_date_format", &self.input_date_format)] {
+            if !formats_dates(format) {
+                return Err(format!("{} '{}' is not a usable date format", key, format));
+            }
+        }
+        Ok(())
+    }
+
+    // Debug builds let DATABASE_URL (from the environment or a .env file) win, it's what the
+    // sqlx macros build against during development. A release build only uses the config,
+    // so a .env in whatever directory day_list is started from can't point it at another database.
+    // The path is given as a file name rather than a URL, where `?`, `#` and `%` mean something.
+    pub fn database_options(&self) -> Result<SqliteConnectOptions, ConfigError> {
+        if cfg!(debug_assertions) {
+            dotenv().ok();
+            if let Ok(url) = env::var("DATABASE_URL") {
+                return SqliteConnectOptions::from_str(&url).map_err(ConfigError::DatabaseUrl);
+            }
+        }
+
+        let path = match &self.database_path {
+            Some(path) => path.clone(),
+            None => data_dir()?.join("day_list.db"),
+        };
+        Ok(SqliteConnectOptions::new().filename(path))
+    }
+}
+
+// chrono panics on display when a format has unknown specifiers or needs a timezone,
+// so try it out on a date first
+fn formats_dates(format: &str) -> bool {
+    use std::fmt::Write;
+
+    let sample = NaiveDate::from_ymd_opt(2026, 1, 2).and_then(|day| day.and_hms_opt(3, 4, 5));
+    let mut written = String::new();
+    sample.is_some_and(|sample| write!(written, "{}", sample.format(format)).is_ok())
+}
+
+// Choices made in the TUI, kept in $XDG_STATE_HOME/day_list/state.toml between sessions
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedState {
+    #[serde(flatten)]
+    pub daylist: DaylistOptions,
+}
+
+impl SavedState {
+    // A missing or unreadable file just means starting over with the defaults
+    pub fn load() -> SavedState {
+        state_dir().ok()
+            .and_then(|dir| fs::read_to_string(dir.join("state.toml")).ok())
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let contents = toml::to_string(self).map_err(io::Error::other)?;
+        let dir = state_dir().map_err(io::Error::other)?;
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("state.toml"), contents)
+    }
+}
+
+pub fn config_dir() -> Result<PathBuf, ConfigError> {
+    Ok(xdg_dir("XDG_CONFIG_HOME", ".config")?.join("day_list"))
+}
+
+pub fn data_dir() -> Result<PathBuf, ConfigError> {
+    Ok(xdg_dir("XDG_DATA_HOME", ".local/share")?.join("day_list"))
+}
+
+pub fn state_dir() -> Result<PathBuf, ConfigError> {
+    Ok(xdg_dir("XDG_STATE_HOME", ".local/state")?.join("day_list"))
+}
+
+// $XDG_* when set, otherwise the spec's fallback under the home folder
+fn xdg_dir(var: &'static str, fallback: &str) -> Result<PathBuf, ConfigError> {
+    let home = env::var_os("HOME").or_else(|| env::var_os("APPDATA")); // APPDATA on Windows
+    dir_from(var, env::var_os(var), home, fallback)
+}
+
+// xdg_dir with the variables already read. Without either there is nowhere sensible
+// to put the files, the current folder would scatter them around.
+fn dir_from(var: &'static str, xdg: Option<OsString>, home: Option<OsString>, fallback: &str) -> Result<PathBuf, ConfigError> {
+    match xdg {
+        Some(dir) if !dir.is_empty() => Ok(PathBuf::from(dir)),
+        _ => {
+            let home = home
+                .filter(|home| !home.is_empty())
+                .ok_or(ConfigError::NoHome(var))?;
+            Ok(PathBuf::from(home).join(fallback))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(toml: &str) -> Result<(), String> {
+        toml::from_str::<Config>(toml).map_err(|e| e.to_string())?.validate()
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert_eq!(config(""), Ok(()));
+        assert_eq!(config("date_format = \"%Y-%m-%d\"\npage_size = 1\ndefault_priority = 9"), Ok(()));
+    }
+
+    #[test]
+    fn rejects_unusable_values() {
+        assert!(config("date_format = \"%Q\"").is_err());
+        assert!(config("input_date_format = \"%H:%M %z\"").is_err());
+        assert!(config("page_size = 0").is_err());
+        assert!(config("default_priority = 0").is_err());
+        assert!(config("default_priority = 10").is_err());
+    }
+
+    #[test]
+    fn picks_the_xdg_dir_then_home() {
+        let dir = |xdg: Option<&str>, home: Option<&str>| dir_from("XDG_DATA_HOME", xdg.map(OsString::from), home.map(OsString::from), ".local/share");
+        assert_eq!(dir(Some("/data"), Some("/home/me")).unwrap(), PathBuf::from("/data"));
+        assert_eq!(dir(Some(""), Some("/home/me")).unwrap(), PathBuf::from("/home/me/.local/share"));
+        assert!(matches!(dir(None, Some("")), Err(ConfigError::NoHome("XDG_DATA_HOME"))));
+        assert!(matches!(dir(None, None), Err(ConfigError::NoHome("XDG_DATA_HOME"))));
+    }
+}
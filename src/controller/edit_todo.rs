@@ -1,11 +1,18 @@
-use crate::model::schema::Todo;
-use chrono::NaiveDateTime;
+use crate::model::schema::{Todo, Project};
+use crate::model::recurrence::Recurrence;
+use crate::model::date_input::parse_date;
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use crate::config::Config;
+use crate::controller::text_input::TextInput;
 
+#[derive(PartialEq)]
 pub enum EditSelection {
     Name,
     Description,
+    Project,
     DateDue,
     ReminderDate,
+    Recurrence,
     Priority,
 }
 
@@ -16,11 +23,20 @@ pub enum EditAction {
 
 pub struct EditTodo {
     pub selection: EditSelection,
-    pub name: String,
-    pub description: String,
-    pub date_due: String,
-    pub reminder_date: String,
+    pub todo_id: i64, // todo being updated, 0 when creating
+    pub name: TextInput,
+    pub description: TextInput,
+    pub project: TextInput,
+    pub date_due: TextInput,
+    pub reminder_date: TextInput,
+    pub recurrence: TextInput,
     pub priority: i64,
+    pub parent_todo: Option<i64>, // set when creating a subtask
+    pub loaded_due: Option<NaiveDateTime>, // dates of the todo being updated, kept to the second while untouched
+    pub loaded_reminder: Option<NaiveDateTime>,
+
+    pub default_priority: i64,
+    pub input_date_format: String,
 }
 
 impl Default for EditTodo {
@@ -31,31 +47,68 @@ impl Default for EditTodo {
 
 impl EditTodo {
     pub fn new() -> EditTodo {
+        EditTodo::from_config(&Config::default())
+    }
+
+    pub fn from_config(config: &Config) -> EditTodo {
         EditTodo {
             selection: EditSelection::Name,
-            name: String::new(),
-            description: String::new(),
-            date_due: String::new(),
-            reminder_date: String::new(),
-            priority: 4,
+            todo_id: 0,
+            name: TextInput::new(),
+            description: TextInput::new(),
+            project: TextInput::new(),
+            date_due: TextInput::new(),
+            reminder_date: TextInput::new(),
+            recurrence: TextInput::new(),
+            priority: config.default_priority,
+            parent_todo: None,
+            loaded_due: None,
+            loaded_reminder: None,
+
+            default_priority: config.default_priority,
+            input_date_format: config.input_date_format.clone(),
         }
     }
 
-    pub fn update_todo(&mut self) -> Todo {
-        // TODO bring in edit_string
-        // TODO set edit string to current todo data
+    // Fill the form with an existing todo so it can be updated in place
+    pub fn load(&mut self, todo: &Todo, project: String) {
+        self.reset();
+        self.todo_id = todo.todo_id;
+        self.name.set(todo.title.clone());
+        self.description.set(todo.description.clone().unwrap_or_default());
+        self.project.set(project);
+        self.date_due.set(todo.date_due.map(|d| d.format(&self.input_date_format).to_string()).unwrap_or_default());
+        self.reminder_date.set(todo.reminder_date.map(|d| d.format(&self.input_date_format).to_string()).unwrap_or_default());
+        self.recurrence.set(todo.recurrence.as_ref().map(|rule| rule.to_string()).unwrap_or_default());
+        self.priority = todo.priority;
+        self.parent_todo = todo.parent_todo;
+        self.loaded_due = todo.date_due;
+        self.loaded_reminder = todo.reminder_date;
+    }
 
+    // New todos started from a day default to the morning of that day
+    pub fn prefill_due(&mut self, day: NaiveDate) {
+        if let Some(due) = day.and_hms_opt(9, 0, 0) {
+            self.date_due.set(due.format(&self.input_date_format).to_string());
+        }
+    }
+
+    pub fn update_todo(&mut self, project_id: Option<i64>) -> Todo {
         let update_todo = Todo {
-            todo_id: 0,
-            title: self.name.clone(), 
-            description: Some(self.description.clone()),
+            todo_id: self.todo_id,
+            title: self.name.to_string(), 
+            description: self.parse_description(),
             date_created: None,
             status: 0,
-            date_due: self.parse_due(), 
-            reminder_date: self.parse_reminder(), 
-            parent_todo: None, 
+            date_due: self.parse_due().ok().flatten(), 
+            reminder_date: self.parse_reminder().ok().flatten(), 
+            parent_todo: self.parent_todo, 
             priority: self.priority, 
-            project_id: None,
+            project_id,
+            recurrence: self.parse_recurrence().ok().flatten(),
+            date_completed: None,
+            archived: false,
+            deleted_at: None,
         };
 
         self.reset();
@@ -63,18 +116,22 @@ impl EditTodo {
         update_todo
     }
 
-    pub fn new_todo(&mut self) -> Todo {
+    pub fn new_todo(&mut self, project_id: Option<i64>) -> Todo {
         let new_todo = Todo {
             todo_id: 0,
-            title: self.name.clone(), 
-            description: Some(self.description.clone()),
+            title: self.name.to_string(), 
+            description: self.parse_description(),
             date_created: None,
             status: 0,
-            date_due: self.parse_due(), 
-            reminder_date: self.parse_reminder(), 
-            parent_todo: None, 
+            date_due: self.parse_due().ok().flatten(), 
+            reminder_date: self.parse_reminder().ok().flatten(), 
+            parent_todo: self.parent_todo, 
             priority: self.priority, 
-            project_id: None,
+            project_id,
+            recurrence: self.parse_recurrence().ok().flatten(),
+            date_completed: None,
+            archived: false,
+            deleted_at: None,
         };
 
         self.reset();
@@ -82,22 +139,140 @@ impl EditTodo {
         new_todo
     }
 
-    fn reset(&mut self) {
+    pub fn reset(&mut self) {
+        self.todo_id = 0;
         self.name.clear();
         self.description.clear();
+        self.project.clear();
         self.date_due.clear();
         self.reminder_date.clear();
-        self.priority = 4; // Magic Number
+        self.recurrence.clear();
+        self.priority = self.default_priority;
+        self.parent_todo = None;
+        self.loaded_due = None;
+        self.loaded_reminder = None;
         self.selection = EditSelection::Name;
     }
 
-    fn parse_due(&self) -> Option<NaiveDateTime> {
-        NaiveDateTime::parse_from_str(self.date_due.as_str(), "%d/%m/%y %H:%M").ok()
+    // The field being typed in, None on Priority which takes a single digit
+    pub fn selected_input(&mut self) -> Option<&mut TextInput> {
+        match self.selection {
+            EditSelection::Name => Some(&mut self.name),
+            EditSelection::Description => Some(&mut self.description),
+            EditSelection::Project => Some(&mut self.project),
+            EditSelection::DateDue => Some(&mut self.date_due),
+            EditSelection::ReminderDate => Some(&mut self.reminder_date),
+            EditSelection::Recurrence => Some(&mut self.recurrence),
+            EditSelection::Priority => None,
+        }
+    }
+
+    // selected_input for drawing the field
+    pub fn selected_field(&self) -> Option<&TextInput> {
+        let input = match self.selection {
+            EditSelection::Name => &self.name,
+            EditSelection::Description => &self.description,
+            EditSelection::Project => &self.project,
+            EditSelection::DateDue => &self.date_due,
+            EditSelection::ReminderDate => &self.reminder_date,
+            EditSelection::Recurrence => &self.recurrence,
+            EditSelection::Priority => return None,
+        };
+        Some(input)
+    }
+
+    // Existing project names starting with what has been typed so far
+    pub fn project_completions<'a>(&self, projects: &'a [Project]) -> Vec<&'a str> {
+        let typed = self.project.value().to_lowercase();
+        projects.iter()
+            .map(|project| project.name.as_str())
+            .filter(|name| name.to_lowercase().starts_with(&typed))
+            .collect()
     }
-    fn parse_reminder(&self) -> Option<NaiveDateTime> {
-        NaiveDateTime::parse_from_str(self.reminder_date.as_str(), "%d/%m/%y %H:%M").ok()
+
+    pub fn complete_project(&mut self, projects: &[Project]) {
+        if let Some(name) = self.project_completions(projects).first() {
+            self.project.set(name.to_string());
+        }
+    }
+
+    // Ok(None) when the field is empty, Err with a message when it can't be read
+    pub fn parse_recurrence(&self) -> Result<Option<Recurrence>, String> {
+        match self.recurrence.value().trim() {
+            "" => Ok(None),
+            rule => rule.parse().map(Some),
+        }
+    }
+
+    // An empty description is no description
+    pub fn parse_description(&self) -> Option<String> {
+        match self.description.value() {
+            "" => None,
+            description => Some(description.to_string()),
+        }
+    }
+
+    // Same as parse_recurrence, relative dates count from now
+    pub fn parse_due(&self) -> Result<Option<NaiveDateTime>, String> {
+        self.parse_date(&self.date_due, self.loaded_due)
+    }
+    pub fn parse_reminder(&self) -> Result<Option<NaiveDateTime>, String> {
+        self.parse_date(&self.reminder_date, self.loaded_reminder)
+    }
+
+    // A loaded date is shown without its seconds, so it is kept as it was unless the field was edited
+    fn parse_date(&self, input: &TextInput, loaded: Option<NaiveDateTime>) -> Result<Option<NaiveDateTime>, String> {
+        match loaded {
+            Some(date) if input.value() == date.format(&self.input_date_format).to_string() => Ok(Some(date)),
+            _ => parse_date(input.value(), &self.input_date_format, Local::now().naive_local()),
+        }
+    }
+
+    // The first field that can't be read, the form isn't saved while there is one
+    pub fn invalid_field(&self) -> Option<EditSelection> {
+        if self.parse_due().is_err() {
+            Some(EditSelection::DateDue)
+        } else if self.parse_reminder().is_err() {
+            Some(EditSelection::ReminderDate)
+        } else if self.parse_recurrence().is_err() {
+            Some(EditSelection::Recurrence)
+        } else {
+            None
+        }
     }
 
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo() -> Todo {
+        Todo {
+            todo_id: 3,
+            date_due: NaiveDate::from_ymd_opt(2026, 11, 2).and_then(|day| day.and_hms_opt(14, 0, 30)),
+            reminder_date: NaiveDate::from_ymd_opt(2026, 11, 2).and_then(|day| day.and_hms_opt(13, 45, 15)),
+            ..Todo::titled("Call the bank")
+        }
+    }
+
+    #[test]
+    fn saving_an_untouched_todo_keeps_its_values() {
+        let mut edit = EditTodo::new();
+        edit.load(&todo(), String::new());
+        let saved = edit.update_todo(None);
+        assert_eq!(saved.description, None);
+        assert_eq!(saved.date_due, todo().date_due);
+        assert_eq!(saved.reminder_date, todo().reminder_date);
+    }
 
+    #[test]
+    fn an_edited_date_is_read_from_the_field() {
+        let mut edit = EditTodo::new();
+        edit.load(&todo(), String::new());
+        edit.date_due.set("02/11/26 15:00");
+        let saved = edit.update_todo(None);
+        assert_eq!(saved.date_due, NaiveDate::from_ymd_opt(2026, 11, 2).and_then(|day| day.and_hms_opt(15, 0, 0)));
+        assert_eq!(saved.reminder_date, todo().reminder_date);
+    }
+}
@@ -1,103 +1,196 @@
-use std::error::Error;
+use crate::error::AppError;
 
+use chrono::{Days, Duration, Local, SubsecRound};
 use crossterm::event::{ KeyCode, KeyEvent, KeyModifiers };
 
-use crate::model::db::Db;
+use crate::config::SavedState;
+use crate::model::db::{Db, GroupMode, ProjectFilter, SmartView};
 use crate::model::schema::Todo;
+use crate::model::error::DbError;
 use crate::controller::nav::Content;
 use crate::controller::nav::Widget;
-use crate::controller::state::AppState;
+use crate::controller::state::{AlertLevel, AppState, Confirm, HistoryPanel};
 use crate::controller::edit_todo::EditSelection;
 use crate::controller::edit_todo::EditAction;
 use crate::controller::state::TodoList;
 
+const NO_TODO_SELECTED: &str = "No valid todo item selected.";
+
 //fn user_events(event: Event, app: AppState, todo_list: TodoList, list_db: &Db) {}
 
 //fn keyboard_events(key: KeyCode, app: AppState, todo_list: TodoList, list_db: &Db) {}
 
 
 pub async fn search_events(key: 
-    KeyEvent, app: &mut AppState, list_db: &Db) -> Result<(), Box<dyn Error>> {
+    KeyEvent, app: &mut AppState, todo_list: &mut TodoList, list_db: &Db) -> Result<(), AppError> {
     match key.code {
         KeyCode::Esc => {
             app.focused_widget = Widget::Main;
-            app.main_content_shown = Content::Daylist;
+            show_content(Content::Daylist, app, todo_list, list_db).await?;
         }, 
-        KeyCode::Char(c) => app.search_string.push(c), // append character to search string
-        KeyCode::Backspace => {app.search_string.pop();}, // remove last character
         KeyCode::Enter => {
             // SUBMIT SEARCH STRING...
-            // TODO update to lazy loading
-            app.search_results = list_db.search(&app.search_string).await?;
-            app.main_content_shown = Content::SearchResults;
+            // Results are paged in like the Daylist
+            app.search_query = app.search_string.to_string();
+            show_content(Content::SearchResults, app, todo_list, list_db).await?;
         }
 
         KeyCode::Up => app.focused_widget = app.focused_widget.up(),
         KeyCode::Down => app.focused_widget = app.focused_widget.down(),
-        KeyCode::Left => app.focused_widget = app.focused_widget.left(),
-        KeyCode::Right => app.focused_widget = app.focused_widget.right(),
-        _ => {} // Handle other keys as needed
+        // Typing, deleting and moving the cursor
+        _ => { app.search_string.handle_key(key); }
     };
     Ok(())
 }
 
 
-pub fn default_events(key: KeyEvent, app: &mut AppState) {
+pub async fn list_events(key: KeyEvent, app: &mut AppState, todo_list: &mut TodoList, list_db: &Db) -> Result<(), AppError> {
     match key.code {
         KeyCode::Char('q') => app.exit(), // Quit on 'q' press
         KeyCode::Char('Q') => app.exit(), // Quit on 'Q' press
-        KeyCode::Esc => app.exit(), // Exit on Escape key - We'll see if this is kept
+        KeyCode::Esc => show_content(Content::Daylist, app, todo_list, list_db).await?,
 
-        KeyCode::Char('k') => app.focused_widget = app.focused_widget.up(),
-        KeyCode::Char('j') => app.focused_widget = app.focused_widget.down(),
-        KeyCode::Char('h') => app.focused_widget = app.focused_widget.left(),
-        KeyCode::Char('l') => app.focused_widget = app.focused_widget.right(),
-        KeyCode::Up => app.focused_widget = app.focused_widget.up(),
-        KeyCode::Down => app.focused_widget = app.focused_widget.down(),
-        KeyCode::Left => app.focused_widget = app.focused_widget.left(),
-        KeyCode::Right => app.focused_widget = app.focused_widget.right(),
-        _ => {}, // Handle other keys as needed
-    }
-}
+        // Trashed todos can only be restored or deleted for good
+        KeyCode::Char('d' | 'e' | 'm' | 's' | 'n') if app.main_content_shown == Content::Trash => {
+            app.alert(AlertLevel::Info, "This is the trash, restore the todo with 'r' first.");
+        }
 
-pub async fn list_events(key: KeyEvent, app: &mut AppState, todo_list: &mut TodoList, list_db: &Db) -> Result<(), Box<dyn Error>> {
-    match key.code {
-        KeyCode::Char('q') => app.exit(), // Quit on 'q' press
-        KeyCode::Char('Q') => app.exit(), // Quit on 'Q' press
-        KeyCode::Esc => app.main_content_shown = Content::Daylist,
+        KeyCode::Char('L') => show_content(Content::Daylist, app, todo_list, list_db).await?,
 
-        KeyCode::Char('L') => todo_list.set_todos(list_db.fetch_todos(app.todo_items_offset, app.todo_items_limit).await?),
+        KeyCode::Char('o') => {
+            app.daylist.sort = app.daylist.sort.next();
+            save_list_order(app);
+            reload_todos(app, todo_list, list_db).await?;
+        }
+        KeyCode::Char('g') => {
+            app.daylist.group = app.daylist.group.next();
+            save_list_order(app);
+            reload_todos(app, todo_list, list_db).await?;
+        }
+        KeyCode::Char('c') => {
+            app.daylist.hide_completed = !app.daylist.hide_completed;
+            save_list_order(app);
+            reload_todos(app, todo_list, list_db).await?;
+        }
+
+        KeyCode::Char('C') => show_content(Content::Completed, app, todo_list, list_db).await?,
+        KeyCode::Char('A') => {
+            // A period reaching back past chrono's range leaves nothing old enough to archive
+            let cutoff = Local::now().naive_local().checked_sub_signed(Duration::days(app.config.archive_after_days.into()));
+            if let Some(cutoff) = cutoff {
+                list_db.archive_completed(cutoff).await?;
+                reload_todos(app, todo_list, list_db).await?;
+            }
+        }
+
+        KeyCode::Char('v') => {
+            // Step through the smart views, starting with Today
+            let view = match app.main_content_shown {
+                Content::Smart(view) => view.next(),
+                _ => SmartView::Today,
+            };
+            show_content(Content::Smart(view), app, todo_list, list_db).await?;
+        }
 
         KeyCode::Char('n') => {
-            app.edit_action = EditAction::Create;
-            app.focused_widget = Widget::EditTodo; 
-            app.main_content_shown = Content::EditTodo;
+            // New todos land in the project being viewed
+            if let ProjectFilter::Project(_) = app.project_list.filter() {
+                app.edit.project.set(app.project_list.filter_name());
+            }
+            // and on the day being viewed
+            if let Content::Day(day) = app.main_content_shown {
+                app.edit.prefill_due(day);
+            }
+            open_editor(EditAction::Create, app);
+        }
+
+        KeyCode::Char('s') => {
+            // Subtasks start out in their parent's project
+            if let Some(parent) = todo_list.selected() {
+                app.edit.parent_todo = Some(parent.todo_id);
+                app.edit.project.set(app.project_list.projects.iter()
+                    .find(|project| Some(project.project_id) == parent.project_id)
+                    .map(|project| project.name.clone())
+                    .unwrap_or_default());
+                open_editor(EditAction::Create, app);
+            }
+        }
+
+        KeyCode::Char(' ') => todo_list.toggle_collapse(),
+
+        KeyCode::Char('m') => {
+            // Pick the destination in the Projects column
+            if let Some(id) = todo_list.get_selected_id() {
+                app.moving_todo = Some(id);
+                app.focused_widget = Widget::Projects;
+            }
         }
 
         KeyCode::Char('e') => {
-            app.edit_action = EditAction::Update;
-            app.focused_widget = Widget::EditTodo; 
-            app.main_content_shown = Content::EditTodo;
+            if let Some(todo) = todo_list.selected() {
+                let project = app.project_list.projects.iter()
+                    .find(|project| Some(project.project_id) == todo.project_id)
+                    .map(|project| project.name.clone())
+                    .unwrap_or_default();
+                app.edit.load(todo, project);
+                open_editor(EditAction::Update, app);
+            }
         }
 
-        KeyCode::Tab => {
-            if key.modifiers.contains(KeyModifiers::SHIFT) {
-                print!("Going up captain!");
+        KeyCode::Tab | KeyCode::BackTab => {
+            if key.code == KeyCode::BackTab || key.modifiers.contains(KeyModifiers::SHIFT) {
                 todo_list.previous();
             } else {
+                // Fetch the next page before running off the end
+                if todo_list.near_end() && !todo_list.exhausted {
+                    load_more_todos(app, todo_list, list_db).await?;
+                }
                 todo_list.next();
             }
         }
 
         KeyCode::Char('d') => {
-            list_db.toggle_todo_status(todo_list.get_selected_id()).await?;
-            todo_list.set_todos(list_db.fetch_todos(app.todo_items_offset, app.todo_items_limit).await?);
+            let Some(id) = todo_list.get_selected_id() else {
+                app.alert(AlertLevel::Info, NO_TODO_SELECTED);
+                return Ok(());
+            };
+            let completed = list_db.toggle_todo_status(id).await?;
+            if let Some(true) = completed {
+                let open = list_db.count_open_subtasks(id).await?;
+                if open > 0 {
+                    app.confirm = Some(Confirm::CompleteSubtasks { todo_id: id, open });
+                } else {
+                    offer_parent_completion(app, id, list_db).await?;
+                }
+            }
+            reload_todos(app, todo_list, list_db).await?;
         },
 
-        KeyCode::Char('X') => {
-            list_db.delete_todo(todo_list.get_selected_id()).await?;
-            todo_list.set_todos(list_db.fetch_todos(app.todo_items_offset, app.todo_items_limit).await?);
+        // In the trash X deletes for good, elsewhere it moves to the trash
+        KeyCode::Char('X') if app.main_content_shown == Content::Trash => {
+            if let Some(todo) = todo_list.selected() {
+                app.confirm = Some(Confirm::Purge { todo_id: todo.todo_id, title: todo.title.clone() });
+            }
+        }
+        KeyCode::Char('X') => match todo_list.selected() {
+            Some(todo) => app.confirm = Some(Confirm::Delete { todo_id: todo.todo_id, title: todo.title.clone() }),
+            None => app.alert(AlertLevel::Info, NO_TODO_SELECTED),
         },
+        KeyCode::Char('H') => {
+            if let Some(todo) = todo_list.selected() {
+                let entries = list_db.fetch_history(todo.todo_id).await?;
+                app.history = Some(HistoryPanel { title: todo.title.clone(), entries, scroll: 0 });
+            }
+        }
+        KeyCode::Char('u') => undo(false, app, todo_list, list_db).await?,
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => undo(true, app, todo_list, list_db).await?,
+        KeyCode::Char('r') if app.main_content_shown == Content::Trash => {
+            if let Some(id) = todo_list.get_selected_id() {
+                list_db.restore_todo(id).await?;
+                reload_todos(app, todo_list, list_db).await?;
+            }
+        }
+        KeyCode::Char('T') => show_content(Content::Trash, app, todo_list, list_db).await?,
 
         KeyCode::Char('k') => app.focused_widget = app.focused_widget.up(),
         KeyCode::Char('j') => app.focused_widget = app.focused_widget.down(),
@@ -112,54 +205,56 @@ pub async fn list_events(key: KeyEvent, app: &mut AppState, todo_list: &mut Todo
     Ok(())
 }
 
-pub async fn edit_events(key: KeyEvent, app: &mut AppState, todo_list: &mut TodoList, list_db: &Db) -> Result<(), Box<dyn Error>> {
+pub async fn edit_events(key: KeyEvent, app: &mut AppState, todo_list: &mut TodoList, list_db: &Db) -> Result<(), AppError> {
+    // Text fields take typing and cursor keys, the rest moves through the form
+    if let Some(input) = app.edit.selected_input() {
+        if input.handle_key(key) {
+            return Ok(());
+        }
+    }
+
     match key.code {
         KeyCode::Esc => {
-            app.main_content_shown = Content::Daylist;
             app.focused_widget = Widget::Main;
-            app.edit.name.clear();
-            app.edit.description.clear();
-            app.edit.date_due.clear();
-            app.edit.reminder_date.clear();
-            app.edit.priority = 4; // Magic Number
+            app.edit.reset();
+            show_content(app.content_before_edit, app, todo_list, list_db).await?;
         },
         KeyCode::Backspace => {
-            match app.edit.selection {
-                EditSelection::Name => app.edit.name.pop(),
-                EditSelection::Description => app.edit.description.pop(),
-                EditSelection::DateDue => app.edit.date_due.pop(),
-                EditSelection::ReminderDate => app.edit.reminder_date.pop(),
-                // That none is there to satisfy the compiler
-                EditSelection::Priority => { app.edit.priority = 0; None}, 
-            };
-        }, // remove last character
+            if let EditSelection::Priority = app.edit.selection {
+                app.edit.priority = 0;
+            }
+        },
         KeyCode::Enter => {
             match app.edit.selection {
                 EditSelection::Name => app.edit.selection = EditSelection::Description,
-                EditSelection::Description => app.edit.selection = EditSelection::DateDue,
+                EditSelection::Description => app.edit.selection = EditSelection::Project,
+                EditSelection::Project => app.edit.selection = EditSelection::DateDue,
                 EditSelection::DateDue => app.edit.selection = EditSelection::ReminderDate,
-                EditSelection::ReminderDate => app.edit.selection = EditSelection::Priority,
+                EditSelection::ReminderDate => app.edit.selection = EditSelection::Recurrence,
+                EditSelection::Recurrence => app.edit.selection = EditSelection::Priority,
                 EditSelection::Priority => {
-                    match app.edit_action {
-                        EditAction::Create => list_db.create_todo(&app.edit.new_todo()).await?,
-                        EditAction::Update => list_db.create_todo(&app.edit.update_todo()).await?,
+                    // Send the user back to whatever can't be read instead of dropping it
+                    if let Some(field) = app.edit.invalid_field() {
+                        app.edit.selection = field;
+                        app.alert(AlertLevel::Warning, "This field can't be read, the todo wasn't saved.");
+                        return Ok(());
                     }
+                    save_edit(app, list_db).await?;
 
-                    app.main_content_shown = Content::Daylist;
                     app.focused_widget = Widget::Main;
+                    // Back to the list the editor was opened from
+                    show_content(app.content_before_edit, app, todo_list, list_db).await?;
                 }
             }
-            // Reload todos
-            todo_list.set_todos(list_db.fetch_todos(app.todo_items_offset, app.todo_items_limit).await?);
         },
-        //  KeyCode::Tab // TODO add tab functionality
+        KeyCode::Tab => {
+            if let EditSelection::Project = app.edit.selection {
+                app.edit.complete_project(&app.project_list.projects);
+            }
+        }
         KeyCode::Char(c) => {
-            match app.edit.selection {
-                EditSelection::Name => app.edit.name.push(c),
-                EditSelection::Description => app.edit.description.push(c),
-                EditSelection::DateDue => app.edit.date_due.push(c),
-                EditSelection::ReminderDate => app.edit.reminder_date.push(c),
-                EditSelection::Priority => match c {
+            if let EditSelection::Priority = app.edit.selection {
+                match c {
                     '1' => app.edit.priority = 1,
                     '2' => app.edit.priority = 2,
                     '3' => app.edit.priority = 3,
@@ -169,7 +264,7 @@ pub async fn edit_events(key: KeyEvent, app: &mut AppState, todo_list: &mut Todo
                     '7' => app.edit.priority = 7,
                     '8' => app.edit.priority = 8,
                     '9' => app.edit.priority = 9,
-                    _ => app.edit.priority = 4,
+                    _ => app.edit.priority = app.edit.default_priority,
                 }
             }
         }, 
@@ -182,3 +277,398 @@ pub async fn edit_events(key: KeyEvent, app: &mut AppState, todo_list: &mut Todo
     }
     Ok(())
 }
+
+pub fn paste_events(text: &str, app: &mut AppState) {
+    if app.popup_shown() {
+        return;
+    }
+    match app.focused_widget {
+        Widget::Search => app.search_string.paste(text),
+        Widget::EditProject => app.project_name.paste(text),
+        Widget::EditTodo => {
+            if let Some(input) = app.edit.selected_input() {
+                input.paste(text);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub async fn calendar_events(key: KeyEvent, app: &mut AppState, todo_list: &mut TodoList, list_db: &Db) -> Result<(), AppError> {
+    let shown_month = app.calendar.month_start();
+    match key.code {
+        KeyCode::Char('q') => app.exit(), // Quit on 'q' press
+        KeyCode::Char('Q') => app.exit(), // Quit on 'Q' press
+        KeyCode::Esc => app.focused_widget = Widget::Main,
+
+        KeyCode::Char('n') => {
+            // New todo due on the selected day
+            app.edit.prefill_due(app.calendar.selected);
+            open_editor(EditAction::Create, app);
+        }
+
+        KeyCode::Enter => {
+            // Agenda of the selected day in the main panel
+            app.focused_widget = Widget::Main;
+            show_content(Content::Day(app.calendar.selected), app, todo_list, list_db).await?;
+        }
+        // Over to the todo list, where Tab goes on through the todos
+        KeyCode::Tab => app.focused_widget = Widget::Main,
+
+        // Arrows move the day, hjkl still move between widgets
+        KeyCode::Left => app.calendar.move_days(-1),
+        KeyCode::Right => app.calendar.move_days(1),
+        KeyCode::Up => app.calendar.move_days(-7),
+        KeyCode::Down => app.calendar.move_days(7),
+        KeyCode::PageUp => app.calendar.previous_month(),
+        KeyCode::PageDown => app.calendar.next_month(),
+
+        KeyCode::Char('k') => app.focused_widget = app.focused_widget.up(),
+        KeyCode::Char('j') => app.focused_widget = app.focused_widget.down(),
+        KeyCode::Char('h') => app.focused_widget = app.focused_widget.left(),
+        KeyCode::Char('l') => app.focused_widget = app.focused_widget.right(),
+        _ => {}, // Handle other keys as needed
+    };
+
+    if app.calendar.month_start() != shown_month {
+        refresh_calendar(app, list_db).await?;
+    }
+    Ok(())
+}
+
+pub async fn upcoming_events(key: KeyEvent, app: &mut AppState, todo_list: &mut TodoList, list_db: &Db) -> Result<(), AppError> {
+    match key.code {
+        KeyCode::Char('q') => app.exit(), // Quit on 'q' press
+        KeyCode::Char('Q') => app.exit(), // Quit on 'Q' press
+        KeyCode::Esc => app.focused_widget = Widget::Main,
+
+        KeyCode::Tab => {
+            app.upcoming_view = app.upcoming_view.next();
+            refresh_upcoming(app, list_db).await?;
+        }
+        KeyCode::BackTab => {
+            app.upcoming_view = app.upcoming_view.previous();
+            refresh_upcoming(app, list_db).await?;
+        }
+
+        KeyCode::Enter => {
+            // The whole view in the main panel
+            app.focused_widget = Widget::Main;
+            show_content(Content::Smart(app.upcoming_view), app, todo_list, list_db).await?;
+        }
+
+        KeyCode::Char('k') => app.focused_widget = app.focused_widget.up(),
+        KeyCode::Char('j') => app.focused_widget = app.focused_widget.down(),
+        KeyCode::Char('h') => app.focused_widget = app.focused_widget.left(),
+        KeyCode::Char('l') => app.focused_widget = app.focused_widget.right(),
+        KeyCode::Up => app.focused_widget = app.focused_widget.up(),
+        KeyCode::Down => app.focused_widget = app.focused_widget.down(),
+        KeyCode::Left => app.focused_widget = app.focused_widget.left(),
+        KeyCode::Right => app.focused_widget = app.focused_widget.right(),
+        _ => {},
+    }
+    Ok(())
+}
+
+pub async fn project_events(key: KeyEvent, app: &mut AppState, todo_list: &mut TodoList, list_db: &Db) -> Result<(), AppError> {
+    match key.code {
+        KeyCode::Char('q') => app.exit(), // Quit on 'q' press
+        KeyCode::Char('Q') => app.exit(), // Quit on 'Q' press
+        KeyCode::Esc => {
+            app.moving_todo = None;
+            app.focused_widget = Widget::Main;
+        }
+
+        KeyCode::Enter => {
+            if let Some(id) = app.moving_todo {
+                let project_id = match app.project_list.filter() {
+                    // Not a destination, the todo keeps waiting for one
+                    ProjectFilter::All => {
+                        app.status = Some(String::from("Pick Inbox or a project to move the todo to"));
+                        return Ok(());
+                    }
+                    ProjectFilter::Inbox => None,
+                    ProjectFilter::Project(project_id) => Some(project_id),
+                };
+                list_db.move_todo(id, project_id).await?;
+                app.moving_todo = None;
+                reload_todos(app, todo_list, list_db).await?;
+                app.focused_widget = Widget::Main;
+            }
+        }
+
+        KeyCode::Char('n') => {
+            app.project_action = EditAction::Create;
+            app.project_name.clear();
+            app.focused_widget = Widget::EditProject;
+        }
+
+        KeyCode::Char('e') => {
+            if let Some(project) = app.project_list.selected() {
+                app.project_action = EditAction::Update;
+                app.project_name.set(project.name.clone());
+                app.focused_widget = Widget::EditProject;
+            }
+        }
+
+        KeyCode::Char('f') => {
+            if let Some(id) = app.project_list.get_selected_id() {
+                list_db.toggle_project_favorite(id).await?;
+                app.project_list.set_projects(list_db.fetch_projects().await?);
+            }
+        }
+
+        KeyCode::Char('X') => {
+            if let Some(project) = app.project_list.selected() {
+                app.confirm = Some(Confirm::DeleteProject { project_id: project.project_id, name: project.name.clone() });
+            }
+        }
+
+        KeyCode::Tab | KeyCode::BackTab => {
+            if key.code == KeyCode::BackTab || key.modifiers.contains(KeyModifiers::SHIFT) {
+                app.project_list.previous();
+            } else {
+                app.project_list.next();
+            }
+            // The Daylist follows the highlighted project
+            show_content(Content::Daylist, app, todo_list, list_db).await?;
+        }
+
+        KeyCode::Char('k') => app.focused_widget = app.focused_widget.up(),
+        KeyCode::Char('j') => app.focused_widget = app.focused_widget.down(),
+        KeyCode::Char('h') => app.focused_widget = app.focused_widget.left(),
+        KeyCode::Char('l') => app.focused_widget = app.focused_widget.right(),
+        KeyCode::Up => app.focused_widget = app.focused_widget.up(),
+        KeyCode::Down => app.focused_widget = app.focused_widget.down(),
+        KeyCode::Left => app.focused_widget = app.focused_widget.left(),
+        KeyCode::Right => app.focused_widget = app.focused_widget.right(),
+        _ => {}, // Handle other keys as needed
+    };
+    Ok(())
+}
+
+pub async fn edit_project_events(key: KeyEvent, app: &mut AppState, list_db: &Db) -> Result<(), AppError> {
+    match key.code {
+        KeyCode::Esc => {
+            app.project_name.clear();
+            app.focused_widget = Widget::Projects;
+        }
+        KeyCode::Enter => {
+            let name = app.project_name.value().trim();
+            if !name.is_empty() {
+                match app.project_action {
+                    EditAction::Create => { list_db.create_project(name).await?; },
+                    EditAction::Update => {
+                        if let Some(id) = app.project_list.get_selected_id() {
+                            list_db.rename_project(id, name).await?;
+                        }
+                    }
+                }
+                app.project_list.set_projects(list_db.fetch_projects().await?);
+            }
+            app.project_name.clear();
+            app.focused_widget = Widget::Projects;
+        }
+        // Typing, deleting and moving the cursor
+        _ => { app.project_name.handle_key(key); }
+    }
+    Ok(())
+}
+
+// Save the todo in the edit form. A project typed in that doesn't exist yet is added
+// along with it, so one undo takes both back.
+async fn save_edit(app: &mut AppState, list_db: &Db) -> Result<(), AppError> {
+    let name = app.edit.project.value().trim().to_string();
+    let existing = app.project_list.projects.iter()
+        .find(|project| project.name.eq_ignore_ascii_case(&name))
+        .map(|project| project.project_id);
+
+    if name.is_empty() || existing.is_some() {
+        match app.edit_action {
+            EditAction::Create => { list_db.create_todo(&app.edit.new_todo(existing)).await?; },
+            EditAction::Update => list_db.update_todo(&app.edit.update_todo(existing)).await?,
+        }
+        return Ok(());
+    }
+
+    match app.edit_action {
+        EditAction::Create => { list_db.create_todo_in_new_project(&app.edit.new_todo(None), &name).await?; },
+        EditAction::Update => list_db.update_todo_in_new_project(&app.edit.update_todo(None), &name).await?,
+    }
+    app.project_list.set_projects(list_db.fetch_projects().await?);
+    Ok(())
+}
+
+// Answer to the question in `app.confirm`
+pub async fn confirm_events(key: KeyEvent, app: &mut AppState, todo_list: &mut TodoList, list_db: &Db) -> Result<(), AppError> {
+    match key.code {
+        KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+            match app.confirm.take() {
+                Some(Confirm::CompleteSubtasks { todo_id, .. }) => {
+                    list_db.complete_subtasks(todo_id).await?;
+                    offer_parent_completion(app, todo_id, list_db).await?;
+                }
+                Some(Confirm::CompleteParent { todo_id, .. }) => {
+                    list_db.complete_todo(todo_id).await?;
+                    // Completing the parent may finish the grandparent as well
+                    offer_parent_completion(app, todo_id, list_db).await?;
+                }
+                Some(Confirm::Purge { todo_id, .. }) => list_db.purge_todo(todo_id).await?,
+                Some(Confirm::Delete { todo_id, .. }) => list_db.delete_todo(todo_id).await?,
+                Some(Confirm::DeleteProject { project_id, .. }) => {
+                    list_db.delete_project(project_id).await?;
+                    app.project_list.set_projects(list_db.fetch_projects().await?);
+                }
+                None => {}
+            }
+            reload_todos(app, todo_list, list_db).await?;
+        }
+        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => app.confirm = None,
+        _ => {},
+    }
+    Ok(())
+}
+
+// Snooze or dismiss the reminder at the front of `app.reminders`
+pub async fn reminder_events(key: KeyEvent, app: &mut AppState, todo_list: &mut TodoList, list_db: &Db) -> Result<(), AppError> {
+    let now = Local::now().naive_local().trunc_subsecs(0);
+    let snooze_until = match key.code {
+        KeyCode::Char('1') => Some(now + Duration::minutes(10)),
+        KeyCode::Char('2') => Some(now + Duration::hours(1)),
+        KeyCode::Char('3') => (now.date() + Days::new(1)).and_hms_opt(9, 0, 0),
+        KeyCode::Enter | KeyCode::Esc => None,
+        _ => return Ok(()),
+    };
+
+    if let Some(todo) = app.reminders.pop_front() {
+        if let Some(until) = snooze_until {
+            list_db.set_reminder(todo.todo_id, Some(until)).await?;
+            reload_todos(app, todo_list, list_db).await?;
+        }
+    }
+    Ok(())
+}
+
+// Dismiss the alert at the front of `app.alerts`
+pub fn alert_events(key: KeyEvent, app: &mut AppState) {
+    if let KeyCode::Enter | KeyCode::Esc | KeyCode::Char(' ') = key.code {
+        app.alerts.pop_front();
+    }
+}
+
+pub fn history_events(key: KeyEvent, app: &mut AppState) {
+    if let Some(panel) = &mut app.history {
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('H') | KeyCode::Char('q') => app.history = None,
+            KeyCode::Up | KeyCode::Char('k') => panel.scroll = panel.scroll.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') if usize::from(panel.scroll) + 1 < panel.entries.len() => panel.scroll += 1,
+            _ => {}
+        }
+    }
+}
+
+// Ask to complete the parent of a just completed todo once all its subtasks are done
+async fn offer_parent_completion(app: &mut AppState, todo_id: i64, list_db: &Db) -> Result<(), AppError> {
+    if let Some(parent) = list_db.parent_ready_to_complete(todo_id).await? {
+        app.confirm = Some(Confirm::CompleteParent { todo_id: parent.todo_id, title: parent.title });
+    }
+    Ok(())
+}
+
+fn open_editor(action: EditAction, app: &mut AppState) {
+    // Remember the list underneath, unless the editor is already showing
+    if app.main_content_shown != Content::EditTodo {
+        app.content_before_edit = app.main_content_shown;
+    }
+    app.edit_action = action;
+    app.focused_widget = Widget::EditTodo; 
+    app.main_content_shown = Content::EditTodo;
+}
+
+// Undo the last change, or redo the last undone one, and say which it was
+async fn undo(redo: bool, app: &mut AppState, todo_list: &mut TodoList, list_db: &Db) -> Result<(), AppError> {
+    let (done, verb) = if redo {
+        (list_db.redo().await?, "Redid")
+    } else {
+        (list_db.undo().await?, "Undid")
+    };
+    app.status = Some(match done {
+        Some(description) => format!("{}: {}", verb, description),
+        None => format!("Nothing to {}", if redo { "redo" } else { "undo" }),
+    });
+
+    // Projects may have come back or gone away too
+    app.project_list.set_projects(list_db.fetch_projects().await?);
+    reload_todos(app, todo_list, list_db).await
+}
+
+// Switch the main panel to another list of todos, starting again from the first page
+pub async fn show_content(content: Content, app: &mut AppState, todo_list: &mut TodoList, list_db: &Db) -> Result<(), AppError> {
+    app.main_content_shown = content;
+    app.todo_items_offset = 0;
+    todo_list.unselect();
+    reload_todos(app, todo_list, list_db).await
+}
+
+// Refresh the todos in the main panel, keeping every page loaded so far
+pub async fn reload_todos(app: &mut AppState, todo_list: &mut TodoList, list_db: &Db) -> Result<(), AppError> {
+    // Only the Daylist is grouped, the other lists keep their own order
+    let group = match app.main_content_shown {
+        Content::Daylist => app.daylist.group,
+        _ => GroupMode::None,
+    };
+    todo_list.set_grouping(group, &app.project_list.projects);
+
+    let limit = app.todo_items_offset.max(app.todo_items_limit);
+    let todos = fetch_page(app, list_db, 0, limit).await?;
+
+    app.todo_items_offset = todos.len() as u32;
+    todo_list.exhausted = todos.len() < limit as usize;
+    todo_list.set_todos(todos);
+    todo_list.progress = list_db.fetch_subtask_progress().await?;
+    refresh_calendar(app, list_db).await?;
+    refresh_upcoming(app, list_db).await?;
+    Ok(())
+}
+
+pub async fn refresh_upcoming(app: &mut AppState, list_db: &Db) -> Result<(), AppError> {
+    app.upcoming_list = list_db.fetch_smart_view(app.upcoming_view, Local::now().date_naive(), 0, app.todo_items_limit).await?;
+    Ok(())
+}
+
+// Sort, group and hiding survive a restart. The list is already rearranged when saving fails,
+// only the next start won't remember it.
+fn save_list_order(app: &mut AppState) {
+    let saved = SavedState { daylist: app.daylist };
+    if let Err(e) = saved.save() {
+        app.alert(AlertLevel::Warning, format!("The list order won't be remembered: {}", e));
+    }
+}
+
+// Mark the days of the shown month that have something due
+pub async fn refresh_calendar(app: &mut AppState, list_db: &Db) -> Result<(), AppError> {
+    let days = list_db.fetch_due_days(app.calendar.month_start(), app.calendar.next_month_start()).await?;
+    app.calendar.due_days = days.into_iter().collect();
+    Ok(())
+}
+
+pub async fn load_more_todos(app: &mut AppState, todo_list: &mut TodoList, list_db: &Db) -> Result<(), AppError> {
+    let todos = fetch_page(app, list_db, app.todo_items_offset, app.todo_items_limit).await?;
+
+    app.todo_items_offset += todos.len() as u32;
+    todo_list.exhausted = todos.len() < app.todo_items_limit as usize;
+    todo_list.append_todos(todos);
+    Ok(())
+}
+
+// The query behind whatever the main panel is showing
+async fn fetch_page(app: &AppState, list_db: &Db, offset: u32, limit: u32) -> Result<Vec<Todo>, DbError> {
+    match app.main_content_shown {
+        Content::SearchResults => list_db.search(&app.search_query, offset, limit).await,
+        Content::Day(day) => list_db.fetch_day(day, offset, limit).await,
+        Content::Smart(view) => list_db.fetch_smart_view(view, Local::now().date_naive(), offset, limit).await,
+        Content::Completed => list_db.fetch_completed(offset, limit).await,
+        Content::Trash => list_db.fetch_trash(offset, limit).await,
+        _ => list_db.fetch_todos(&app.project_list.filter(), &app.daylist, offset, limit).await,
+    }
+}
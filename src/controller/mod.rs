@@ -2,3 +2,4 @@ pub mod nav;
 pub mod state;
 pub mod handle;
 pub mod edit_todo;
+pub mod text_input;
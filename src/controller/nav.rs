@@ -1,12 +1,22 @@
+use chrono::NaiveDate;
+
+use crate::model::db::SmartView;
+
+#[derive(Clone, Copy, PartialEq)]
 pub enum Content {
     Daylist,
     EditTodo,
     SearchResults,
+    Day(NaiveDate), // agenda of a day picked in the calendar
+    Smart(SmartView),
+    Completed, // most recently completed first, archived todos included
+    Trash,
 }
 
 pub enum Widget {
     Calendar,
     EditTodo,
+    EditProject,
     Main,
     Search,
     Upcoming,
@@ -1,4 +1,4 @@
-use tui::widgets::{Block, Borders, Paragraph, List, ListItem, Table, Row, Cell};
+use tui::widgets::{Block, Borders, Paragraph, List, ListItem, Table};
 use tui::style::{Color, Style};
 use tui::layout::{Constraint, Direction, Layout, Rect};
 
@@ -6,53 +6,142 @@ use crate::controller::nav::Widget;
 use crate::controller::nav::Content;
 use crate::LOGO4;
 use crate::controller::edit_todo::EditTodo;
+use crate::controller::text_input::TextInput;
 use crate::controller::edit_todo::EditAction;
+use crate::config::Config;
+use crate::model::db::SmartView;
+use crate::model::history::HistoryEntry;
+use chrono::{Datelike, Days, Local, Months, NaiveDate};
+
+
+// Follow-up actions the user is asked about before they happen
+pub enum Confirm {
+    CompleteSubtasks { todo_id: i64, open: i64 },
+    CompleteParent { todo_id: i64, title: String },
+    Purge { todo_id: i64, title: String },
+    Delete { todo_id: i64, title: String },
+    DeleteProject { project_id: i64, name: String },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AlertLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+impl AlertLevel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlertLevel::Info => "Info",
+            AlertLevel::Warning => "Warning",
+            AlertLevel::Error => "Error",
+        }
+    }
+}
+
+// A message shown in a popup until dismissed
+pub struct Alert {
+    pub level: AlertLevel,
+    pub message: String,
+}
+
+// Changes made to one todo, shown over the lists until closed
+pub struct HistoryPanel {
+    pub title: String,
+    pub entries: Vec<HistoryEntry>, // newest first
+    pub scroll: u16,
+}
 
+impl Confirm {
+    pub fn question(&self) -> String {
+        match self {
+            Confirm::CompleteSubtasks { open, .. } => format!("Also complete {} open subtask(s)?", open),
+            Confirm::CompleteParent { title, .. } => format!("All subtasks done. Complete \"{}\" too?", title),
+            Confirm::Purge { title, .. } => format!("Delete \"{}\" for good?", title),
+            Confirm::Delete { title, .. } => format!("Move \"{}\" and its subtasks to the trash?", title),
+            Confirm::DeleteProject { name, .. } => format!("Delete project \"{}\"? Its todos move to the inbox.", name),
+        }
+    }
+}
 
 pub struct AppState {
     running: bool,
-    pub todo_list: TodoList,
+    pub config: Config,
 
-    pub search_string: String,
+    pub search_string: TextInput,
     pub main_context_string: String,
-    pub search_results: Vec<Todo>,
+    pub search_query: String, // last submitted search, what SearchResults shows
 
     pub edit: EditTodo,
     pub edit_action: EditAction,
 
     pub focused_widget: Widget,
     pub main_content_shown: Content, // component state
+    pub content_before_edit: Content, // where to go back to when the editor closes
+
+    pub daylist: DaylistOptions, // sort, group and hiding, kept between sessions
 
     pub todo_items_limit: u32, // component state
-    pub todo_items_offset: u32, // component state
+    pub todo_items_offset: u32, // component state - todos loaded so far, where the next page starts
 
     pub upcoming_list: Vec<Todo>,
+    pub upcoming_view: SmartView, // what the Upcoming widget lists
+
+    pub project_list: ProjectList,
+    pub project_name: TextInput,
+    pub project_action: EditAction,
+    pub moving_todo: Option<i64>, // todo waiting for a destination project
+    pub confirm: Option<Confirm>, // question waiting for a y/n answer
+    pub reminders: VecDeque<Todo>, // reminders gone off, the front one is shown
+    pub status: Option<String>, // outcome of the last undo or redo, shown until the next key
+    pub history: Option<HistoryPanel>,
+    pub alerts: VecDeque<Alert>, // messages waiting to be dismissed, the front one is shown
+
+    pub calendar: CalendarState,
     // ...
 }
 
 impl AppState {
-    pub fn init() -> AppState {
+    pub fn init(config: Config) -> AppState {
         // TODO
         // do a bunch of calculations...
 
         AppState {
             running: true,
-            todo_list: TodoList::new(Vec::new()),
 
-            search_string: String::new(),
+            search_string: TextInput::new(),
             main_context_string: String::new(),
-            search_results: vec![],
+            search_query: String::new(),
 
-            edit: EditTodo::new(),
+            edit: EditTodo::from_config(&config),
             edit_action: EditAction::Create,
 
             focused_widget: Widget::Main,
             main_content_shown: Content::Daylist,
+            content_before_edit: Content::Daylist,
+
+            daylist: DaylistOptions::default(),
 
-            todo_items_limit: 10,
+            todo_items_limit: config.page_size,
             todo_items_offset: 0,
 
             upcoming_list: vec![],
+            upcoming_view: SmartView::Next7Days,
+
+            project_list: ProjectList::new(Vec::new()),
+            project_name: TextInput::new(),
+            project_action: EditAction::Create,
+            moving_todo: None,
+            confirm: None,
+            reminders: VecDeque::new(),
+            status: None,
+            history: None,
+            alerts: VecDeque::new(),
+
+            calendar: CalendarState::new(Local::now().date_naive()),
+
+            config,
         }
     }
 
@@ -62,6 +151,15 @@ impl AppState {
     pub fn exit(&mut self) {
         self.running = false;
     }
+
+    // Something is drawn over the panels and takes the keys
+    pub fn popup_shown(&self) -> bool {
+        !self.alerts.is_empty() || !self.reminders.is_empty() || self.history.is_some() || self.confirm.is_some()
+    }
+
+    pub fn alert(&mut self, level: AlertLevel, message: impl Into<String>) {
+        self.alerts.push_back(Alert { level, message: message.into() });
+    }
 }
 
 pub struct LayoutState<'a> {
@@ -77,12 +175,13 @@ pub struct LayoutState<'a> {
     pub upcoming_content: List<'a>,
     pub calendar_content: Table<'a>,
     pub projects_content: List<'a>,
-    pub bottom_row_content: Table<'a>,
+    pub bottom_row_content: Paragraph<'a>,
 
     pub search_bounds: Rect,
     pub main_bounds: Rect,
     pub calendar_bounds: Rect,
     pub upcoming_bounds: Rect,
+    pub projects_bounds: Rect,
 
 }
 
@@ -117,16 +216,8 @@ impl LayoutState<'_> {
                     Constraint::Percentage(15),
                 ]),
 
-            bottom_row_content: Table::new(vec![])
-                .block(Block::default().borders(Borders::ALL))
-                .widths(&[
-                    Constraint::Percentage(10),
-                    Constraint::Percentage(10),
-                    Constraint::Percentage(10),
-                    Constraint::Percentage(15),
-                    Constraint::Percentage(15),
-                    Constraint::Percentage(15),
-                ]),
+            bottom_row_content: Paragraph::new(String::from(""))
+                .block(Block::default().borders(Borders::ALL)),
 
             projects_content: List::new(vec![])
                 .block(Block::default().title("Projects").borders(Borders::ALL))
@@ -137,6 +228,7 @@ impl LayoutState<'_> {
             main_bounds: Rect::default(),
             calendar_bounds: Rect::default(),
             upcoming_bounds: Rect::default(),
+            projects_bounds: Rect::default(),
         }
     }
 
@@ -145,16 +237,18 @@ impl LayoutState<'_> {
         self.main_bounds = self.center_column[1];
         self.upcoming_bounds = self.right_column[0];
         self.calendar_bounds = self.right_column[1];
+        self.projects_bounds = self.left_column[1];
     }
 
-    pub fn structure(&mut self, frame_size: Rect) {
+    // `keybind_rows` is how many lines the keyboard shortcuts take
+    pub fn structure(&mut self, frame_size: Rect, keybind_rows: u16) {
         // Split the screen into vertical chunks
         self.chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints(
                 [
                     Constraint::Min(0), // main section
-                    Constraint::Length(3), // Bottom row for keyboard shortcuts
+                    Constraint::Length(keybind_rows + 2), // Bottom row for keyboard shortcuts
                 ]
                     .as_ref(),
             )
@@ -213,32 +307,256 @@ impl LayoutState<'_> {
     } 
 }
 
+use std::collections::{HashMap, HashSet, VecDeque};
 use tui::widgets::ListState;
 use crate::model::schema::Todo;
+
+// One todo in the Daylist tree
+pub struct TreeRow {
+    pub index: usize, // position in `todos`
+    pub depth: usize,
+    pub has_children: bool,
+}
+
+// One visible line of the Daylist
+pub enum ListRow {
+    Group(String), // header above the todos of a group, can't be selected
+    Todo(TreeRow),
+}
+
 pub struct TodoList {
     pub todos: Vec<Todo>,
+    pub rows: Vec<ListRow>, // display order, collapsed branches left out
+    pub collapsed: HashSet<i64>,
+    pub progress: HashMap<i64, (i64, i64)>, // (done, total) subtasks by parent id
+    pub exhausted: bool, // every page has been loaded
+    pub group: GroupMode, // todos are expected to arrive already ordered by group
+    pub project_names: HashMap<i64, String>, // for the project group headers
     pub state: ListState,
 }
 
 impl TodoList {
     pub fn new(todos: Vec<Todo>) -> TodoList {
-        TodoList {
+        let mut todo_list = TodoList {
             todos,
+            rows: Vec::new(),
+            collapsed: HashSet::new(),
+            progress: HashMap::new(),
+            exhausted: false,
+            group: GroupMode::None,
+            project_names: HashMap::new(),
             state: ListState::default(),
-        }
+        };
+        todo_list.build_tree();
+        todo_list
     }
 
+    // Keeps the selected todo selected if it is still in the list
     pub fn set_todos(&mut self, todos: Vec<Todo>) {
+        let selected_id = self.get_selected_id();
         self.todos = todos;
+        self.build_tree();
         self.state = ListState::default(); // Reset the state since the items have changed
+        self.select_id(selected_id);
+    }
+
+    // Add the next page below what is already loaded
+    pub fn append_todos(&mut self, todos: Vec<Todo>) {
+        let selected_id = self.get_selected_id();
+        self.todos.extend(todos);
+        self.build_tree();
+        self.select_id(selected_id);
+    }
+
+    // Takes effect with the next set_todos
+    pub fn set_grouping(&mut self, group: GroupMode, projects: &[Project]) {
+        self.group = group;
+        self.project_names = projects.iter()
+            .map(|project| (project.project_id, project.name.clone()))
+            .collect();
+    }
+
+    // Close enough to the bottom that the next page should be fetched
+    pub fn near_end(&self) -> bool {
+        match self.state.selected() {
+            Some(i) => i + 3 >= self.rows.len(),
+            None => self.rows.is_empty(),
+        }
+    }
+
+    // Lay the todos out parent first, children indented below. A todo whose parent isn't
+    // loaded is shown as a root. Subtasks stay under their parent whatever group they'd be in.
+    fn build_tree(&mut self) {
+        let positions: HashMap<i64, usize> = self.todos.iter()
+            .enumerate()
+            .map(|(i, todo)| (todo.todo_id, i))
+            .collect();
+
+        let mut children: HashMap<i64, Vec<usize>> = HashMap::new();
+        let mut roots = Vec::new();
+        for (i, todo) in self.todos.iter().enumerate() {
+            match todo.parent_todo {
+                Some(parent) if parent != todo.todo_id && positions.contains_key(&parent) =>
+                    children.entry(parent).or_default().push(i),
+                _ => roots.push(i),
+            }
+        }
+
+        self.rows.clear();
+        let mut visited = HashSet::new();
+        let mut current_group: Option<String> = None;
+        // Depth first, reversed so the first child is popped first
+        let mut stack: Vec<(usize, usize)> = roots.into_iter().rev().map(|i| (i, 0)).collect();
+        while let Some((i, depth)) = stack.pop() {
+            let id = self.todos[i].todo_id;
+            if !visited.insert(id) { continue; } // guard against parent cycles
+
+            if depth == 0 {
+                if let Some(label) = self.group_label(&self.todos[i]) {
+                    if current_group.as_ref() != Some(&label) {
+                        self.rows.push(ListRow::Group(label.clone()));
+                        current_group = Some(label);
+                    }
+                }
+            }
+
+            let kids = children.get(&id);
+            self.rows.push(ListRow::Todo(TreeRow { index: i, depth, has_children: kids.is_some() }));
+
+            if let Some(kids) = kids {
+                if !self.collapsed.contains(&id) {
+                    stack.extend(kids.iter().rev().map(|&k| (k, depth + 1)));
+                }
+            }
+        }
+    }
+
+    // Header of the group a todo falls in, None when not grouping
+    fn group_label(&self, todo: &Todo) -> Option<String> {
+        let label = match self.group {
+            GroupMode::None => return None,
+            GroupMode::Project => todo.project_id
+                .and_then(|id| self.project_names.get(&id).cloned())
+                .unwrap_or_else(|| String::from("Inbox")),
+            GroupMode::Priority => format!("Priority {}", todo.priority),
+            GroupMode::DueDay => todo.date_due
+                .map(|due| due.format("%a %d %b %Y").to_string())
+                .unwrap_or_else(|| String::from("No due date")),
+            GroupMode::Status => String::from(if todo.status == 1 { "Done" } else { "Open" }),
+        };
+        Some(label)
     }
 
     // Select the next item. This will not be reflected until the widget is drawn in the
     // `Terminal::draw` callback using `Frame::render_stateful_widget`.
+    pub fn next(&mut self) {
+        self.step(1);
+    }
+
+    // Select the previous item. This will not be reflected until the widget is drawn in the
+    // `Terminal::draw` callback using `Frame::render_stateful_widget`.
+    pub fn previous(&mut self) {
+        self.step(self.rows.len().saturating_sub(1));
+    }
+
+    // Move `by` rows forward, wrapping around and jumping over group headers
+    fn step(&mut self, by: usize) {
+        let len = self.rows.len();
+        let mut i = match self.state.selected() {
+            Some(i) => (i + by) % len.max(1),
+            None => 0,
+        };
+        for _ in 0..len {
+            if let ListRow::Todo(_) = self.rows[i] {
+                self.state.select(Some(i));
+                return;
+            }
+            i = (i + by) % len;
+        }
+    }
+
+    // Unselect the currently selected item if any. The implementation of `ListState` makes
+    // sure that the stored offset is also reset.
+    pub fn unselect(&mut self) {
+        self.state.select(None);
+    }
+
+    // Fold or unfold the children of the selected todo
+    pub fn toggle_collapse(&mut self) {
+        let row = match self.selected_row() {
+            Some(row) if row.has_children => row,
+            _ => return,
+        };
+        let id = self.todos[row.index].todo_id;
+        if !self.collapsed.remove(&id) {
+            self.collapsed.insert(id);
+        }
+        self.build_tree();
+        self.select_id(Some(id));
+    }
+
+    fn select_id(&mut self, id: Option<i64>) {
+        let position = id.and_then(|id| self.rows.iter().position(|row| match row {
+            ListRow::Todo(row) => self.todos[row.index].todo_id == id,
+            ListRow::Group(_) => false,
+        }));
+        self.state.select(position);
+    }
+
+    fn selected_row(&self) -> Option<&TreeRow> {
+        match self.state.selected().and_then(|i| self.rows.get(i)) {
+            Some(ListRow::Todo(row)) => Some(row),
+            _ => None,
+        }
+    }
+
+    pub fn selected(&self) -> Option<&Todo> {
+        self.selected_row().map(|row| &self.todos[row.index])
+    }
+
+    pub fn get_selected_id(&self) -> Option<i64> {
+        self.selected().map(|todo| todo.todo_id)
+    }
+}
+
+use crate::model::schema::Project;
+use crate::model::db::{DaylistOptions, GroupMode, ProjectFilter};
+
+// Rows above the real projects: "All" and "Inbox (no project)"
+pub const PSEUDO_PROJECTS: usize = 2;
+
+pub struct ProjectList {
+    pub projects: Vec<Project>,
+    pub state: ListState,
+}
+
+impl ProjectList {
+    pub fn new(projects: Vec<Project>) -> ProjectList {
+        ProjectList {
+            projects,
+            state: ListState::default(),
+        }
+    }
+
+    // Keeps the selection where it was so renaming or favoriting doesn't jump the cursor
+    pub fn set_projects(&mut self, projects: Vec<Project>) {
+        self.projects = projects;
+        if let Some(i) = self.state.selected() {
+            if i >= self.len() {
+                self.state.select(Some(self.len() - 1));
+            }
+        }
+    }
+
+    // Number of rows shown, pseudo entries included
+    fn len(&self) -> usize {
+        self.projects.len() + PSEUDO_PROJECTS
+    }
+
     pub fn next(&mut self) {
         let i = match self.state.selected() {
             Some(i) => {
-                if i >= self.todos.len() - 1 {
+                if i >= self.len() - 1 {
                     0
                 } else {
                     i + 1
@@ -249,13 +567,11 @@ impl TodoList {
         self.state.select(Some(i));
     }
 
-    // Select the previous item. This will not be reflected until the widget is drawn in the
-    // `Terminal::draw` callback using `Frame::render_stateful_widget`.
     pub fn previous(&mut self) {
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
-                    self.todos.len() - 1
+                    self.len() - 1
                 } else {
                     i - 1
                 }
@@ -265,18 +581,200 @@ impl TodoList {
         self.state.select(Some(i));
     }
 
-    // Unselect the currently selected item if any. The implementation of `ListState` makes
-    // sure that the stored offset is also reset.
-    pub fn unselect(&mut self) {
-        self.state.select(None);
+    // The highlighted real project, None when on a pseudo entry
+    pub fn selected(&self) -> Option<&Project> {
+        self.state.selected()
+            .and_then(|i| i.checked_sub(PSEUDO_PROJECTS))
+            .and_then(|i| self.projects.get(i))
     }
 
     pub fn get_selected_id(&self) -> Option<i64> {
+        self.selected().map(|project| project.project_id)
+    }
+
+    pub fn filter(&self) -> ProjectFilter {
         match self.state.selected() {
-            Some(i) => {
-                Some(self.todos[i].todo_id)
+            None | Some(0) => ProjectFilter::All,
+            Some(1) => ProjectFilter::Inbox,
+            Some(_) => match self.get_selected_id() {
+                Some(id) => ProjectFilter::Project(id),
+                None => ProjectFilter::All,
+            },
+        }
+    }
+
+    // Label of the current filter, used as the Daylist title
+    pub fn filter_name(&self) -> String {
+        match self.filter() {
+            ProjectFilter::All => String::from("All"),
+            ProjectFilter::Inbox => String::from("Inbox"),
+            ProjectFilter::Project(_) => self.selected().map(|p| p.name.clone()).unwrap_or_default(),
+        }
+    }
+}
+
+pub struct CalendarState {
+    pub selected: NaiveDate,
+    pub due_days: HashSet<NaiveDate>, // days in the shown month with open todos due
+}
+
+impl CalendarState {
+    pub fn new(today: NaiveDate) -> CalendarState {
+        CalendarState {
+            selected: today,
+            due_days: HashSet::new(),
+        }
+    }
+
+    // Negative to go back
+    pub fn move_days(&mut self, days: i64) {
+        let moved = match days >= 0 {
+            true => self.selected.checked_add_days(Days::new(days as u64)),
+            false => self.selected.checked_sub_days(Days::new(days.unsigned_abs())),
+        };
+        if let Some(day) = moved {
+            self.selected = day;
+        }
+    }
+
+    // chrono clamps the day, Jan 31st + 1 month is Feb 28th/29th
+    pub fn next_month(&mut self) {
+        if let Some(day) = self.selected.checked_add_months(Months::new(1)) {
+            self.selected = day;
+        }
+    }
+
+    pub fn previous_month(&mut self) {
+        if let Some(day) = self.selected.checked_sub_months(Months::new(1)) {
+            self.selected = day;
+        }
+    }
+
+    pub fn month_start(&self) -> NaiveDate {
+        self.selected.with_day(1).unwrap_or(self.selected)
+    }
+
+    pub fn next_month_start(&self) -> NaiveDate {
+        self.month_start().checked_add_months(Months::new(1)).unwrap_or(self.selected)
+    }
+
+    // Weeks of the shown month, Sunday first, None where the day belongs to another month
+    pub fn weeks(&self) -> Vec<[Option<NaiveDate>; 7]> {
+        let start = self.month_start();
+        let end = self.next_month_start();
+        let lead = start.weekday().num_days_from_sunday() as u64;
+
+        let mut weeks = Vec::new();
+        let mut week = [None; 7];
+        let mut day = start;
+        let mut column = lead as usize;
+        while day < end {
+            week[column] = Some(day);
+            column += 1;
+            if column == 7 {
+                weeks.push(week);
+                week = [None; 7];
+                column = 0;
             }
-            None => None
+            day = match day.succ_opt() {
+                Some(next) => next,
+                None => break,
+            };
         }
+        if column > 0 {
+            weeks.push(week);
+        }
+        weeks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn todo(todo_id: i64, parent_todo: Option<i64>, priority: i64) -> Todo {
+        Todo { todo_id, parent_todo, priority, ..Todo::titled(&format!("todo {}", todo_id)) }
+    }
+
+    // The rows as "# header" and todo ids indented by depth
+    fn rows(todo_list: &TodoList) -> Vec<String> {
+        todo_list.rows.iter().map(|row| match row {
+            ListRow::Group(label) => format!("# {}", label),
+            ListRow::Todo(row) => format!("{}{}", "  ".repeat(row.depth), todo_list.todos[row.index].todo_id),
+        }).collect()
+    }
+
+    #[test]
+    fn weeks_pad_the_month_to_whole_weeks() {
+        // October 2026 starts on a Thursday and ends on a Saturday
+        let weeks = CalendarState::new(day(2026, 10, 18)).weeks();
+        assert_eq!(weeks.len(), 5);
+        assert_eq!(weeks[0], [None, None, None, None, Some(day(2026, 10, 1)), Some(day(2026, 10, 2)), Some(day(2026, 10, 3))]);
+        assert_eq!(weeks[4][6], Some(day(2026, 10, 31)));
+
+        // February 2026 starts on a Sunday and fills exactly four weeks
+        let weeks = CalendarState::new(day(2026, 2, 10)).weeks();
+        assert_eq!(weeks.len(), 4);
+        assert_eq!(weeks[0][0], Some(day(2026, 2, 1)));
+        assert_eq!(weeks[3][6], Some(day(2026, 2, 28)));
+    }
+
+    #[test]
+    fn weeks_of_a_leap_february() {
+        let weeks = CalendarState::new(day(2028, 2, 29)).weeks();
+        let days: Vec<NaiveDate> = weeks.iter().flatten().flatten().copied().collect();
+        assert_eq!(days.len(), 29);
+        assert_eq!(days.last(), Some(&day(2028, 2, 29)));
+    }
+
+    #[test]
+    fn build_tree_puts_subtasks_under_their_parent() {
+        // Loaded out of order, 4 is a subtask of a parent that isn't loaded
+        let todo_list = TodoList::new(vec![
+            todo(3, Some(1), 4),
+            todo(1, None, 4),
+            todo(4, Some(9), 4),
+            todo(2, Some(1), 4),
+            todo(5, Some(3), 4),
+        ]);
+        assert_eq!(rows(&todo_list), ["1", "  3", "    5", "  2", "4"]);
+    }
+
+    #[test]
+    fn build_tree_leaves_out_collapsed_branches() {
+        let mut todo_list = TodoList::new(vec![todo(1, None, 4), todo(2, Some(1), 4), todo(3, None, 4)]);
+        todo_list.collapsed.insert(1);
+        todo_list.build_tree();
+        assert_eq!(rows(&todo_list), ["1", "3"]);
+    }
+
+    #[test]
+    fn build_tree_shows_its_own_parent_as_a_root() {
+        let todo_list = TodoList::new(vec![todo(1, Some(1), 4), todo(2, Some(1), 4)]);
+        assert_eq!(rows(&todo_list), ["1", "  2"]);
+    }
+
+    #[test]
+    fn grouping_adds_a_header_per_group() {
+        let mut todo_list = TodoList::new(Vec::new());
+        todo_list.set_grouping(GroupMode::Priority, &[]);
+        // Subtasks stay under their parent even in another priority
+        todo_list.set_todos(vec![todo(1, None, 1), todo(2, Some(1), 5), todo(3, None, 1), todo(4, None, 5)]);
+        assert_eq!(rows(&todo_list), ["# Priority 1", "1", "  2", "3", "# Priority 5", "4"]);
+    }
+
+    #[test]
+    fn grouping_by_project_names_the_projects() {
+        let projects = [Project { project_id: 7, name: String::from("Home"), description: None, color: None, favorite_status: false }];
+        let mut todo_list = TodoList::new(Vec::new());
+        todo_list.set_grouping(GroupMode::Project, &projects);
+        let mut home = todo(2, None, 4);
+        home.project_id = Some(7);
+        todo_list.set_todos(vec![todo(1, None, 4), home]);
+        assert_eq!(rows(&todo_list), ["# Inbox", "1", "# Home", "2"]);
     }
 }
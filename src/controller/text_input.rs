@@ -0,0 +1,222 @@
+use std::fmt;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+// A single line of editable text with a cursor, used by the search box, the edit form and project names
+#[derive(Default, Clone)]
+pub struct TextInput {
+    text: String,
+    cursor: usize, // byte offset, always on a char boundary
+}
+
+impl TextInput {
+    pub fn new() -> TextInput {
+        TextInput::default()
+    }
+
+    pub fn value(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    // Replace the text, the cursor goes to the end
+    pub fn set(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.set(String::new());
+    }
+
+    // The text that fits in a box `width` columns wide, split at the cursor. Scrolled so the
+    // cursor stays in the box with a column left for it, and cut off where the box ends.
+    pub fn view(&self, width: u16) -> (&str, &str) {
+        let width = usize::from(width.max(1));
+        let mut start = 0;
+        while self.text[start..self.cursor].width() >= width {
+            start = self.text[start..].chars().next().map_or(self.cursor, |c| start + c.len_utf8());
+        }
+
+        let mut end = self.cursor;
+        let mut used = self.text[start..self.cursor].width();
+        for c in self.text[self.cursor..].chars() {
+            used += c.width().unwrap_or(0);
+            if used > width {
+                break;
+            }
+            end += c.len_utf8();
+        }
+        (&self.text[start..self.cursor], &self.text[self.cursor..end])
+    }
+
+    // Edit or move the cursor. Returns false for keys the input has no use for,
+    // those are left to the caller (Enter, Esc, Tab, Up/Down...).
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('w') if control => self.delete_word(),
+            KeyCode::Char(_) if control => return false,
+            KeyCode::Char(c) => self.insert(c),
+            KeyCode::Backspace => {
+                if let Some(previous) = self.previous_boundary() {
+                    self.text.replace_range(previous..self.cursor, "");
+                    self.cursor = previous;
+                }
+            }
+            KeyCode::Delete => {
+                if let Some(next) = self.next_boundary() {
+                    self.text.replace_range(self.cursor..next, "");
+                }
+            }
+            KeyCode::Left => self.cursor = self.previous_boundary().unwrap_or(self.cursor),
+            KeyCode::Right => self.cursor = self.next_boundary().unwrap_or(self.cursor),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.len(),
+            _ => return false,
+        }
+        true
+    }
+
+    // Pasted text lands at the cursor, line breaks become spaces as the input is a single line
+    pub fn paste(&mut self, text: &str) {
+        let line: String = text.trim_end_matches(['\r', '\n'])
+            .chars()
+            .map(|c| if c == '\n' || c == '\r' || c == '\t' { ' ' } else { c })
+            .filter(|c| !c.is_control())
+            .collect();
+        self.text.insert_str(self.cursor, &line);
+        self.cursor += line.len();
+    }
+
+    fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    // Ctrl-W: back over any spaces, then the word before them
+    fn delete_word(&mut self) {
+        let before = &self.text[..self.cursor];
+        let trimmed = before.trim_end();
+        let start = trimmed.rfind(char::is_whitespace)
+            .map(|index| index + trimmed[index..].chars().next().map_or(1, char::len_utf8))
+            .unwrap_or(0);
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    fn previous_boundary(&self) -> Option<usize> {
+        self.text[..self.cursor].chars().next_back().map(|c| self.cursor - c.len_utf8())
+    }
+
+    fn next_boundary(&self) -> Option<usize> {
+        self.text[self.cursor..].chars().next().map(|c| self.cursor + c.len_utf8())
+    }
+}
+
+impl fmt::Display for TextInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(text: &str, cursor: usize) -> TextInput {
+        TextInput { text: text.to_string(), cursor }
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn edits_between_multibyte_characters() {
+        let mut input = TextInput::new();
+        input.set("café→日本");
+        input.handle_key(key(KeyCode::Left));
+        input.handle_key(key(KeyCode::Left));
+        input.handle_key(key(KeyCode::Backspace)); // the arrow
+        input.handle_key(key(KeyCode::Char('ü')));
+        assert_eq!(input.value(), "caféü日本");
+        assert_eq!(input.view(20).0, "caféü");
+
+        input.handle_key(key(KeyCode::Delete));
+        assert_eq!(input.value(), "caféü本");
+        input.handle_key(key(KeyCode::Home));
+        input.handle_key(key(KeyCode::Right));
+        input.handle_key(key(KeyCode::Right));
+        input.handle_key(key(KeyCode::Right));
+        input.handle_key(key(KeyCode::Delete));
+        assert_eq!(input.value(), "cafü本");
+    }
+
+    #[test]
+    fn cursor_stops_at_the_ends() {
+        let mut input = TextInput::new();
+        input.set("é");
+        input.handle_key(key(KeyCode::Right));
+        input.handle_key(key(KeyCode::Delete));
+        assert_eq!(input.value(), "é");
+        input.handle_key(key(KeyCode::Home));
+        input.handle_key(key(KeyCode::Left));
+        input.handle_key(key(KeyCode::Backspace));
+        assert_eq!(input.value(), "é");
+    }
+
+    #[test]
+    fn ctrl_w_deletes_the_word_before_the_cursor() {
+        let mut input = TextInput::new();
+        input.set("buy crème brûlée  ");
+        assert!(input.handle_key(KeyEvent::new(KeyCode::Char('w'), KeyModifiers::CONTROL)));
+        assert_eq!(input.value(), "buy crème ");
+        input.handle_key(KeyEvent::new(KeyCode::Char('w'), KeyModifiers::CONTROL));
+        assert_eq!(input.value(), "buy ");
+    }
+
+    #[test]
+    fn paste_lands_at_the_cursor_on_one_line() {
+        let mut input = TextInput::new();
+        input.set("ab");
+        input.handle_key(key(KeyCode::Left));
+        input.paste("ü\nx\t\r\n");
+        assert_eq!(input.value(), "aü x b");
+        input.handle_key(key(KeyCode::Char('!')));
+        assert_eq!(input.value(), "aü x !b");
+    }
+
+    #[test]
+    fn keys_left_to_the_caller() {
+        let mut input = TextInput::new();
+        assert!(!input.handle_key(key(KeyCode::Enter)));
+        assert!(!input.handle_key(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL)));
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn view_fits_short_text() {
+        assert_eq!(input("milk", 2).view(10), ("mi", "lk"));
+        assert_eq!(input("milk", 4).view(10), ("milk", ""));
+    }
+
+    #[test]
+    fn view_scrolls_to_the_cursor() {
+        // A column is left for the cursor after the last character
+        assert_eq!(input("abcdefghij", 10).view(4), ("hij", ""));
+        assert_eq!(input("abcdefghij", 5).view(4), ("cde", "f"));
+        assert_eq!(input("abcdefghij", 0).view(4), ("", "abcd"));
+    }
+
+    #[test]
+    fn view_counts_wide_characters() {
+        // 日本語 takes six columns
+        assert_eq!(input("日本語", 9).view(4), ("語", ""));
+        assert_eq!(input("日本語", 0).view(5), ("", "日本"));
+    }
+}
@@ -0,0 +1,64 @@
+use std::fmt;
+use std::io;
+
+use crate::model::error::DbError;
+use crate::reminder::ReminderError;
+
+// Errors that reach the TUI. Most are shown in a popup and the app carries on,
+// a broken terminal is the one thing it can't recover from.
+#[derive(Debug)]
+pub enum AppError {
+    Db(DbError),
+    Reminder(ReminderError), // from the reminder task running alongside the TUI
+    Terminal(io::Error), // drawing or reading input failed
+}
+
+impl AppError {
+    // Whether the app can keep running after reporting the error
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            AppError::Db(DbError::Sqlx(sqlx::Error::PoolClosed)) => false,
+            AppError::Db(_) => true,
+            AppError::Reminder(e) => !matches!(e, ReminderError::Db(DbError::Sqlx(sqlx::Error::PoolClosed))),
+            AppError::Terminal(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Db(e) => write!(f, "database error: {}", e),
+            AppError::Reminder(e) => write!(f, "{}", e),
+            AppError::Terminal(e) => write!(f, "terminal error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::Db(e) => Some(e),
+            AppError::Reminder(e) => Some(e),
+            AppError::Terminal(e) => Some(e),
+        }
+    }
+}
+
+impl From<DbError> for AppError {
+    fn from(e: DbError) -> Self {
+        AppError::Db(e)
+    }
+}
+
+impl From<ReminderError> for AppError {
+    fn from(e: ReminderError) -> Self {
+        AppError::Reminder(e)
+    }
+}
+
+impl From<io::Error> for AppError {
+    fn from(e: io::Error) -> Self {
+        AppError::Terminal(e)
+    }
+}
@@ -1,6 +1,10 @@
 pub mod model;
 pub mod view;
 pub mod controller;
+pub mod config;
+pub mod cli;
+pub mod reminder;
+pub mod error;
 
 
 //https://patorjk.com/software/taag/#p=display&f=Tmplr&t=Daylist
@@ -35,19 +39,34 @@ pub const LOGO4: &str = "
 ";
 
 
+// Extra context a todo can't know about itself
+pub struct FormatOptions<'a> {
+    pub progress: Option<(i64, i64)>, // (done, total) subtasks
+    pub date_format: &'a str,
+}
+
+impl Default for FormatOptions<'_> {
+    fn default() -> Self {
+        FormatOptions {
+            progress: None,
+            date_format: crate::config::DEFAULT_DATE_FORMAT,
+        }
+    }
+}
+
 impl crate::model::schema::Todo {
-    pub fn format(&self, /* options */) -> String {
+    pub fn format(&self, options: &FormatOptions) -> String {
         let mut todo_status = "[ ]";
         let description = match self.description.clone() {
             Some(s) => s,
             None => "--".to_string(),
         }; 
         let date_due = match self.date_due {
-            Some(d) => d.format("%d/%m/%Y %H:%M:%S").to_string(),
+            Some(d) => d.format(options.date_format).to_string(),
             _ => String::from("invalid date")
         };
         let reminder_date = match self.reminder_date {
-            Some(d) => d.format("%d/%m/%Y %H:%M:%S").to_string(),
+            Some(d) => d.format(options.date_format).to_string(),
             _ => String::from("invalid date")
         };
 
@@ -56,14 +75,24 @@ impl crate::model::schema::Todo {
             todo_status = "[]";
         }
 
-        format!("\n   {} {}\n       {}\n    {}\n    {}\n    {}\n",
-            todo_status, self.title, description,
+        let mut progress = match options.progress {
+            Some((done, total)) => format!(" {}/{}", done, total),
+            None => String::new(),
+        };
+        if let Some(rule) = &self.recurrence {
+            progress.push_str(&format!(" ↻ {}", rule));
+        }
+        if let (1, Some(completed)) = (self.status, self.date_completed) {
+            progress.push_str(&format!(" ✓ {}", completed.format(options.date_format)));
+        }
+        if self.archived {
+            progress.push_str(" (archived)");
+        }
+
+        format!("\n   {} {}{}\n       {}\n    {}\n    {}\n    {}\n",
+            todo_status, self.title, progress, description,
             reminder_date, date_due, self.priority
         )
     }
 }
 
-pub mod utils {
-    // TODO display popup message for user
-    pub fn alert(message: &str) {}
-}
@@ -4,29 +4,87 @@ use std::io;
 use tui::Terminal;
 use tui::backend::CrosstermBackend;
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
-use crossterm::event::{DisableMouseCapture, EnableMouseCapture,};
+use crossterm::event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture};
 
 use day_list::view::render;
+use day_list::model::db::Db;
+use day_list::model::history::Source;
+use day_list::config::Config;
+use day_list::cli;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
+    // Load settings and open the database before touching the terminal so errors print normally
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("day_list: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let database_options = match config.database_options() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("day_list: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let list_db = match Db::new(database_options).await {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("day_list: could not open the database: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Subcommands run without the TUI, and their changes are recorded as coming from the CLI
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let list_db = if args.is_empty() { list_db } else { list_db.with_source(Source::Cli) };
+
+    if !args.is_empty() {
+        if let Err(e) = cli::run(&args, &list_db, &config).await {
+            eprintln!("day_list: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    cli::purge_expired_trash(&list_db, &config).await;
+
+    // Put the shell back before a panic message is printed
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        default_hook(info);
+    }));
+
     // Setup Terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    crossterm::execute!(stdout, EnableMouseCapture)?;
+    crossterm::execute!(stdout, EnableMouseCapture, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     terminal.clear()?;
 
     // running loop
-    render::run(&mut terminal).await.expect("Daylist encountered an error...");
+    let result = render::run(&mut terminal, list_db, config).await;
 
     // Cleanup
-    disable_raw_mode()?;
-    crossterm::execute!(terminal.backend_mut(), DisableMouseCapture)?;
-    terminal.show_cursor()?;
+    restore_terminal()?;
 
+    if let Err(e) = result {
+        eprintln!("day_list: {}", e);
+        std::process::exit(1);
+    }
     Ok(())
 }
 
+// Undo the terminal setup, also used when panicking
+fn restore_terminal() -> io::Result<()> {
+    disable_raw_mode()?;
+    crossterm::execute!(io::stdout(), DisableMouseCapture, DisableBracketedPaste, crossterm::cursor::Show)
+}
+
@@ -0,0 +1,255 @@
+use chrono::{Datelike, Days, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, SubsecRound, Weekday};
+
+// Shown next to date fields that can't be read
+pub const DATE_INPUT_HELP: &str = "e.g. tomorrow 9am, next fri, in 3 days, eod, 2026-11-02 14:00";
+
+// Time used when only a day is given
+const DEFAULT_HOUR: u32 = 9;
+// What "eod" means
+const END_OF_DAY_HOUR: u32 = 17;
+
+// Read a typed date relative to `now`.
+// Ok(None) when the input is empty, Err with a message when it can't be read.
+//
+// Accepted:
+//   the configured format, YYYY-MM-DD [HH:MM[:SS]]
+//   now, eod, in <n> minutes|hours|days|weeks|months
+//   [today|tomorrow|<weekday>|next <weekday>|next week|next month|YYYY-MM-DD] [at] [9am|9:30pm|21:00|noon|midnight]
+// A weekday is the next one from today on, "next" skips today. Without a time the day starts at 09:00,
+// a time alone is today, or tomorrow once it has passed.
+pub fn parse_date(input: &str, format: &str, now: NaiveDateTime) -> Result<Option<NaiveDateTime>, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(None);
+    }
+    let now = now.trunc_subsecs(0);
+
+    for format in [format, "%Y-%m-%d %H:%M", "%Y-%m-%d %H:%M:%S"] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(input, format) {
+            return Ok(Some(date));
+        }
+    }
+
+    let lowercase = input.to_lowercase();
+    let words: Vec<&str> = lowercase.split_whitespace().collect();
+    match words.as_slice() {
+        ["now"] => return Ok(Some(now)),
+        ["in", amount, unit] => return in_amount(amount, unit, now).map(Some),
+        _ => {}
+    }
+
+    let today = now.date();
+    let mut day: Option<NaiveDate> = None;
+    let mut time: Option<NaiveTime> = None;
+
+    let mut words = words.into_iter().peekable();
+    while let Some(word) = words.next() {
+        let parsed_day = match word {
+            "at" | "on" => continue,
+            "today" | "tod" => Some(today),
+            "tomorrow" | "tmr" | "tmrw" => today.succ_opt(),
+            "eod" => {
+                time = time.or(NaiveTime::from_hms_opt(END_OF_DAY_HOUR, 0, 0));
+                Some(today)
+            }
+            "next" => match words.next() {
+                Some("week") => today.checked_add_days(Days::new(7)),
+                Some("month") => today.checked_add_months(Months::new(1)),
+                Some(name) => match parse_weekday(name) {
+                    Some(weekday) => Some(next_weekday(today.succ_opt().unwrap_or(today), weekday)),
+                    None => return Err(format!("next what? '{}'", name)),
+                },
+                None => return Err(String::from("next what?")),
+            },
+            _ => {
+                if let Some(weekday) = parse_weekday(word) {
+                    Some(next_weekday(today, weekday))
+                } else if let Ok(date) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+                    Some(date)
+                } else {
+                    // "9 am" is read as one word
+                    let joined = match words.peek() {
+                        Some(&suffix @ ("am" | "pm")) => {
+                            words.next();
+                            format!("{}{}", word, suffix)
+                        }
+                        _ => word.to_string(),
+                    };
+                    match parse_time(&joined) {
+                        Some(parsed) if time.is_none() => {
+                            time = Some(parsed);
+                            continue;
+                        }
+                        Some(_) => return Err(String::from("two times given")),
+                        None => return Err(format!("can't read '{}'", joined)),
+                    }
+                }
+            }
+        };
+
+        if day.is_some() {
+            return Err(String::from("two days given"));
+        }
+        day = Some(parsed_day.ok_or("date out of range")?);
+    }
+
+    let date = match (day, time) {
+        (Some(day), Some(time)) => day.and_time(time),
+        (Some(day), None) => day.and_time(default_time()),
+        (None, Some(time)) if today.and_time(time) > now => today.and_time(time),
+        (None, Some(time)) => today.succ_opt().ok_or("date out of range")?.and_time(time),
+        (None, None) => return Err(format!("can't read '{}'", input)),
+    };
+    Ok(Some(date))
+}
+
+fn default_time() -> NaiveTime {
+    NaiveTime::from_hms_opt(DEFAULT_HOUR, 0, 0).unwrap_or(NaiveTime::MIN)
+}
+
+// "in 3 days": minutes and hours count from now, longer amounts start the day at the default time
+fn in_amount(amount: &str, unit: &str, now: NaiveDateTime) -> Result<NaiveDateTime, String> {
+    let amount: u32 = amount.parse().map_err(|_| format!("'{}' is not a number", amount))?;
+    let today = now.date();
+    let date = match unit.trim_end_matches('s') {
+        "min" | "minute" | "m" => now.checked_add_signed(Duration::minutes(amount.into())),
+        "hour" | "hr" | "h" => now.checked_add_signed(Duration::hours(amount.into())),
+        "day" | "d" => today.checked_add_days(Days::new(amount.into())).map(|day| day.and_time(default_time())),
+        "week" | "w" => today.checked_add_days(Days::new(u64::from(amount) * 7)).map(|day| day.and_time(default_time())),
+        "month" => today.checked_add_months(Months::new(amount)).map(|day| day.and_time(default_time())),
+        _ => return Err(format!("unknown unit '{}'", unit)),
+    };
+    date.ok_or_else(|| String::from("date out of range"))
+}
+
+// 9am, 9:30pm, 21:00, noon, midnight
+fn parse_time(word: &str) -> Option<NaiveTime> {
+    match word {
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return Some(NaiveTime::MIN),
+        _ => {}
+    }
+
+    let (clock, offset) = match (word.strip_suffix("am"), word.strip_suffix("pm")) {
+        (Some(clock), _) => (clock, Some(0)),
+        (_, Some(clock)) => (clock, Some(12)),
+        _ => (word, None),
+    };
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        None if offset.is_some() => (clock.parse::<u32>().ok()?, 0),
+        None => return None, // a bare number is too ambiguous
+    };
+
+    let hour = match offset {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some(offset) => hour % 12 + offset,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tues" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thur" | "thurs" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+// The first `weekday` on or after `from`
+fn next_weekday(from: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead = (7 + weekday.num_days_from_monday() - from.weekday().num_days_from_monday()) % 7;
+    from + Days::new(ahead.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORMAT: &str = "%d/%m/%Y %H:%M";
+
+    // Wednesday
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 14).unwrap().and_hms_opt(15, 30, 20).unwrap()
+    }
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> Option<NaiveDateTime> {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap().and_hms_opt(hour, minute, 0)
+    }
+
+    fn parse(input: &str) -> Result<Option<NaiveDateTime>, String> {
+        parse_date(input, FORMAT, now())
+    }
+
+    #[test]
+    fn empty_input_is_no_date() {
+        assert_eq!(parse(""), Ok(None));
+        assert_eq!(parse("   "), Ok(None));
+    }
+
+    #[test]
+    fn configured_format_and_iso() {
+        assert_eq!(parse("02/11/2026 14:00"), Ok(at(2026, 11, 2, 14, 0)));
+        assert_eq!(parse("2026-11-02 14:00"), Ok(at(2026, 11, 2, 14, 0)));
+        assert_eq!(parse("2026-11-02 14:00:30"), Ok(NaiveDate::from_ymd_opt(2026, 11, 2).unwrap().and_hms_opt(14, 0, 30)));
+        assert_eq!(parse("2026-11-02"), Ok(at(2026, 11, 2, 9, 0)));
+    }
+
+    #[test]
+    fn relative_amounts() {
+        assert_eq!(parse("now"), Ok(NaiveDate::from_ymd_opt(2026, 10, 14).unwrap().and_hms_opt(15, 30, 20)));
+        assert_eq!(parse("in 10 min"), Ok(NaiveDate::from_ymd_opt(2026, 10, 14).unwrap().and_hms_opt(15, 40, 20)));
+        assert_eq!(parse("in 2 hours"), Ok(NaiveDate::from_ymd_opt(2026, 10, 14).unwrap().and_hms_opt(17, 30, 20)));
+        assert_eq!(parse("in 3 days"), Ok(at(2026, 10, 17, 9, 0)));
+        assert_eq!(parse("in 1 week"), Ok(at(2026, 10, 21, 9, 0)));
+        assert_eq!(parse("in 2 months"), Ok(at(2026, 12, 14, 9, 0)));
+        assert!(parse("in x days").is_err());
+        assert!(parse("in 3 fortnights").is_err());
+    }
+
+    #[test]
+    fn days_and_weekdays() {
+        assert_eq!(parse("today"), Ok(at(2026, 10, 14, 9, 0)));
+        assert_eq!(parse("tomorrow"), Ok(at(2026, 10, 15, 9, 0)));
+        assert_eq!(parse("eod"), Ok(at(2026, 10, 14, 17, 0)));
+        // the next one from today on, "next" skips today
+        assert_eq!(parse("wed"), Ok(at(2026, 10, 14, 9, 0)));
+        assert_eq!(parse("next wednesday"), Ok(at(2026, 10, 21, 9, 0)));
+        assert_eq!(parse("fri"), Ok(at(2026, 10, 16, 9, 0)));
+        assert_eq!(parse("mon"), Ok(at(2026, 10, 19, 9, 0)));
+        assert_eq!(parse("next week"), Ok(at(2026, 10, 21, 9, 0)));
+        assert_eq!(parse("next month"), Ok(at(2026, 11, 14, 9, 0)));
+        assert!(parse("next blursday").is_err());
+        assert!(parse("today tomorrow").is_err());
+    }
+
+    #[test]
+    fn times() {
+        assert_eq!(parse("tomorrow 9am"), Ok(at(2026, 10, 15, 9, 0)));
+        assert_eq!(parse("tomorrow at 9 pm"), Ok(at(2026, 10, 15, 21, 0)));
+        assert_eq!(parse("fri 9:30pm"), Ok(at(2026, 10, 16, 21, 30)));
+        assert_eq!(parse("2026-11-02 at noon"), Ok(at(2026, 11, 2, 12, 0)));
+        assert_eq!(parse("today midnight"), Ok(at(2026, 10, 14, 0, 0)));
+        assert_eq!(parse("12am tomorrow"), Ok(at(2026, 10, 15, 0, 0)));
+        // a time alone is today, or tomorrow once it has passed
+        assert_eq!(parse("21:00"), Ok(at(2026, 10, 14, 21, 0)));
+        assert_eq!(parse("9am"), Ok(at(2026, 10, 15, 9, 0)));
+        assert!(parse("13pm").is_err());
+        assert!(parse("9").is_err());
+        assert!(parse("9am 10am").is_err());
+    }
+
+    #[test]
+    fn overflow_is_an_error() {
+        assert_eq!(parse("in 4000000000 hours"), Err(String::from("date out of range")));
+        assert_eq!(parse("in 4000000000 days"), Err(String::from("date out of range")));
+        assert_eq!(parse("in 4000000000 weeks"), Err(String::from("date out of range")));
+        assert_eq!(parse("in 4000000000 months"), Err(String::from("date out of range")));
+    }
+}
@@ -1,169 +1,1075 @@
-use sqlx::sqlite::SqlitePool;
-use dotenv::dotenv;
-use std::env;
-use chrono::{NaiveDateTime, Local};
+use std::collections::HashMap;
 
-use crate::utils;
-use crate::model::schema::Todo;
+use serde::{Deserialize, Serialize};
+use sqlx::migrate::{Migrate, Migration, Migrator};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+use sqlx::SqliteConnection;
+use chrono::{Days, NaiveDate, NaiveDateTime, NaiveTime, Local};
 
+use crate::model::schema::{Todo, Project};
+use crate::model::history::Source;
+use crate::model::error::DbError;
+
+// Map a `SELECT * FROM todo` row from `sqlx::query!` to a Todo. A macro because every
+// query! call returns its own anonymous row type.
+macro_rules! todo_from_row {
+    ($row:expr) => {{
+        let row = $row;
+        Todo {
+            todo_id: row.todo_id,
+            title: row.title,
+            description: row.description,
+            date_created: Db::string_to_naive_datetime(row.date_created),
+            status: row.status as i8, // Assuming status is stored as BOOLEAN (0 or 1)
+            date_due: Db::string_to_naive_datetime(row.date_due),
+            reminder_date: Db::string_to_naive_datetime(row.reminder_date),
+            parent_todo: row.parent_todo,
+            priority: row.priority,
+            project_id: row.project_id,
+            recurrence: row.recurrence.and_then(|rule| rule.parse().ok()),
+            date_completed: Db::string_to_naive_datetime(row.date_completed),
+            archived: row.archived,
+            deleted_at: Db::string_to_naive_datetime(row.deleted_at),
+        }
+    }};
+}
+pub(crate) use todo_from_row;
+
+// How the Daylist is laid out, kept between sessions
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DaylistOptions {
+    pub sort: SortMode,
+    pub group: GroupMode,
+    pub hide_completed: bool,
+}
+
+// Order of the Daylist, see Db::fetch_todos
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortMode {
+    #[default]
+    Created,
+    Priority,
+    DueDate,
+    Title,
+    Status,
+}
+
+impl SortMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SortMode::Created => "created",
+            SortMode::Priority => "priority",
+            SortMode::DueDate => "due date",
+            SortMode::Title => "title",
+            SortMode::Status => "status",
+        }
+    }
+
+    pub fn next(&self) -> SortMode {
+        match self {
+            SortMode::Created => SortMode::Priority,
+            SortMode::Priority => SortMode::DueDate,
+            SortMode::DueDate => SortMode::Title,
+            SortMode::Title => SortMode::Status,
+            SortMode::Status => SortMode::Created,
+        }
+    }
+
+    // Value matched in the ORDER BY of fetch_todos
+    fn key(&self) -> &'static str {
+        match self {
+            SortMode::Created => "created",
+            SortMode::Priority => "priority",
+            SortMode::DueDate => "due",
+            SortMode::Title => "title",
+            SortMode::Status => "status",
+        }
+    }
+}
+
+// Headers the Daylist is split under
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupMode {
+    #[default]
+    None,
+    Project,
+    Priority,
+    DueDay,
+    Status,
+}
+
+impl GroupMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GroupMode::None => "none",
+            GroupMode::Project => "project",
+            GroupMode::Priority => "priority",
+            GroupMode::DueDay => "due day",
+            GroupMode::Status => "status",
+        }
+    }
+
+    pub fn next(&self) -> GroupMode {
+        match self {
+            GroupMode::None => GroupMode::Project,
+            GroupMode::Project => GroupMode::Priority,
+            GroupMode::Priority => GroupMode::DueDay,
+            GroupMode::DueDay => GroupMode::Status,
+            GroupMode::Status => GroupMode::None,
+        }
+    }
+
+    fn key(&self) -> &'static str {
+        match self {
+            GroupMode::None => "none",
+            GroupMode::Project => "project",
+            GroupMode::Priority => "priority",
+            GroupMode::DueDay => "due",
+            GroupMode::Status => "status",
+        }
+    }
+}
+
+// Open todos picked by due date, see Db::fetch_smart_view
+#[derive(Clone, Copy, PartialEq)]
+pub enum SmartView {
+    Overdue, // due before today
+    Today,
+    Next7Days, // due tomorrow up to a week from today
+    NoDueDate,
+}
+
+impl SmartView {
+    pub const ALL: [SmartView; 4] = [SmartView::Overdue, SmartView::Today, SmartView::Next7Days, SmartView::NoDueDate];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SmartView::Overdue => "Overdue",
+            SmartView::Today => "Today",
+            SmartView::Next7Days => "Next 7 days",
+            SmartView::NoDueDate => "No due date",
+        }
+    }
+
+    pub fn next(&self) -> SmartView {
+        let i = SmartView::ALL.iter().position(|view| view == self).unwrap_or(0);
+        SmartView::ALL[(i + 1) % SmartView::ALL.len()]
+    }
+
+    pub fn previous(&self) -> SmartView {
+        let i = SmartView::ALL.iter().position(|view| view == self).unwrap_or(0);
+        SmartView::ALL[(i + SmartView::ALL.len() - 1) % SmartView::ALL.len()]
+    }
+
+    // Due dates in [from, to), None for an open end
+    fn due_range(&self, today: NaiveDate) -> (Option<NaiveDateTime>, Option<NaiveDateTime>) {
+        let day_start = |days: u64| today.checked_add_days(Days::new(days)).map(|day| day.and_time(NaiveTime::MIN));
+        match self {
+            SmartView::Overdue => (None, day_start(0)),
+            SmartView::Today => (day_start(0), day_start(1)),
+            SmartView::Next7Days => (day_start(1), day_start(8)),
+            SmartView::NoDueDate => (None, None),
+        }
+    }
+}
+
+// Everything in migrations/, compiled into the binary
+static MIGRATOR: Migrator = sqlx::migrate!();
+
+const INIT: i64 = 20241029010615;
+const DATES_TO_TEXT: i64 = 20250225174732;
+
+// What each migration leaves in the schema, to tell how far a database set up by hand
+// (with sqlite3 rather than sqlx, so without a migration history) has got
+const MIGRATION_MARKERS: &[(i64, &str)] = &[
+    (INIT, "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'todo'"),
+    (DATES_TO_TEXT, "SELECT 1 FROM pragma_table_info('todo') WHERE name = 'date_created' AND type = 'TEXT'"),
+    (20261018090000, "SELECT 1 FROM pragma_table_info('todo') WHERE name = 'recurrence'"),
+    (20261018100000, "SELECT 1 FROM pragma_table_info('todo') WHERE name = 'archived'"),
+    (20261018110000, "SELECT 1 FROM pragma_table_info('todo') WHERE name = 'deleted_at'"),
+    (20261018120000, "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'undo_log'"),
+    (20261018130000, "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'todo_history'"),
+];
+
+// Record the migrations a hand-made schema already has as applied, so MIGRATOR.run
+// doesn't try to create tables that are there and only runs the ones still missing
+async fn baseline_migrations(conn: &SqlitePool) -> Result<(), DbError> {
+    let mut tx = conn.begin().await?;
+    tx.ensure_migrations_table().await?;
+    if !tx.list_applied_migrations().await?.is_empty() {
+        return Ok(());
+    }
+
+    for migration in MIGRATOR.iter() {
+        let marker = MIGRATION_MARKERS.iter().find(|(version, _)| *version == migration.version);
+        let Some((_, marker)) = marker else { break };
+        if sqlx::query(marker).fetch_optional(&mut *tx).await?.is_none() {
+            break;
+        }
+        record_migration(&mut tx, migration).await?;
+    }
+
+    Ok(tx.commit().await?)
+}
+
+async fn record_migration(conn: &mut SqliteConnection, migration: &Migration) -> Result<(), DbError> {
+    sqlx::query("INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time)
+VALUES (?, ?, TRUE, ?, 0)")
+        .bind(migration.version)
+        .bind(&*migration.description)
+        .bind(&*migration.checksum)
+        .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+// The dates_to_text migration recreates the todo table without its rows. Run it here instead
+// when the first schema is all a database has, with the todos copied across. Dates stored
+// as unix time become text like every other date.
+async fn migrate_dates_keeping_todos(conn: &SqlitePool) -> Result<(), DbError> {
+    let mut tx = conn.begin().await?;
+    let applied: Vec<i64> = tx.list_applied_migrations().await?.iter().map(|migration| migration.version).collect();
+    let migration = match MIGRATOR.iter().find(|migration| migration.version == DATES_TO_TEXT) {
+        Some(migration) if applied.contains(&INIT) && !applied.contains(&DATES_TO_TEXT) => migration,
+        _ => return Ok(()),
+    };
+
+    // Subtasks may come before their parent
+    sqlx::query("PRAGMA defer_foreign_keys = ON").execute(&mut *tx).await?;
+    sqlx::query("CREATE TEMP TABLE old_todo AS SELECT * FROM todo").execute(&mut *tx).await?;
+    sqlx::raw_sql(&migration.sql).execute(&mut *tx).await?;
+    sqlx::query("INSERT INTO todo (todo_id, title, description, date_created, status, date_due, reminder_date, parent_todo, priority, project_id)
+SELECT todo_id, title, description,
+    CASE typeof(date_created) WHEN 'integer' THEN datetime(date_created, 'unixepoch') ELSE date_created END,
+    status,
+    CASE typeof(date_due) WHEN 'integer' THEN datetime(date_due, 'unixepoch') ELSE date_due END,
+    CASE typeof(reminder_date) WHEN 'integer' THEN datetime(reminder_date, 'unixepoch') ELSE reminder_date END,
+    parent_todo, priority, project_id
+FROM temp.old_todo")
+        .execute(&mut *tx)
+    .await?;
+    sqlx::query("DROP TABLE temp.old_todo").execute(&mut *tx).await?;
+    record_migration(&mut tx, migration).await?;
+
+    Ok(tx.commit().await?)
+}
+
+// Which todos the Daylist shows, picked from the Projects column
+pub enum ProjectFilter {
+    All,
+    Inbox, // todos without a project
+    Project(i64),
+}
+
+#[derive(Clone)]
 pub struct Db {
     pub conn_pool: SqlitePool,
+    pub source: Source, // written to the history of every todo changed through this handle
 }
 
 impl Db {
-    // Connect database to app runtime
-    pub async fn new() -> Db {
-        // Load environment variables - database related
-        dotenv().ok(); 
-        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-        let conn = SqlitePool::connect(&database_url).await.expect("Failed to connect to database"); 
-        // ERROR handler more gracefully
+    // Connect database to app runtime, creating the file and schema on first launch
+    pub async fn new(options: SqliteConnectOptions) -> Result<Db, DbError> {
+        let options = options.create_if_missing(true);
 
-        // Return connection pool for use throughout program
-        Db {
-            conn_pool: conn
+        // SQLite creates the file but not the folders leading to it
+        if let Some(dir) = options.get_filename().parent() {
+            if !dir.as_os_str().is_empty() {
+                std::fs::create_dir_all(dir).map_err(sqlx::Error::Io)?;
+            }
         }
+
+        let conn = SqlitePool::connect_with(options).await?;
+        baseline_migrations(&conn).await?;
+        migrate_dates_keeping_todos(&conn).await?;
+        MIGRATOR.run(&conn).await?;
+
+        // Return connection pool for use throughout program
+        Ok(Db {
+            conn_pool: conn,
+            source: Source::Tui,
+        })
+    }
+
+    pub fn with_source(self, source: Source) -> Db {
+        Db { source, ..self }
     }
 
     // Execute SELECT query on database to get todos
-    pub async fn search(&self, search_string: &String) -> Result<Vec<Todo>, sqlx::Error> {
+    pub async fn search(&self, search_string: &str, offset: u32, limit: u32) -> Result<Vec<Todo>, DbError> {
         // All database functions must return a Result<T>
         let search_string1 = format!("%{}%",search_string);
         let search_string2 = format!("%{}%",search_string);
 
         let rows = sqlx::query!(
-            "SELECT * FROM todo WHERE todo.title LIKE ? OR todo.description LIKE ?;", 
-            search_string1, search_string2)
+            "SELECT * FROM todo WHERE (todo.title LIKE ? OR todo.description LIKE ?) AND deleted_at IS NULL ORDER BY todo_id LIMIT ? OFFSET ?;", 
+            search_string1, search_string2, limit, offset)
             .fetch_all(&self.conn_pool)
         .await?;
 
         // Map the results to Todo
-        let todos: Vec<Todo> = rows.into_iter().map(|row| {
-            Todo {
-                todo_id: row.todo_id,
-                title: row.title,
-                description: row.description,
-                date_created: Db::string_to_naive_datetime(row.date_created),
-                status: row.status as i8, // Assuming status is stored as BOOLEAN (0 or 1)
-                date_due: Db::string_to_naive_datetime(row.date_due),
-                reminder_date: Db::string_to_naive_datetime(row.reminder_date),
-                parent_todo: row.parent_todo,
-                priority: row.priority,
-                project_id: row.project_id,
-            }
-        }).collect();
+        let todos: Vec<Todo> = rows.into_iter().map(|row| todo_from_row!(row)).collect();
 
         Ok(todos)
     }
-    // Execute SELECT query on database to get todos
-    pub async fn fetch_todos(&self, offset: u32, limit: u32) -> Result<Vec<Todo>, sqlx::Error> {
+    pub async fn fetch_todo(&self, id: i64) -> Result<Option<Todo>, DbError> {
+        let row = sqlx::query!("SELECT * FROM todo WHERE todo_id = ?", id)
+            .fetch_optional(&self.conn_pool)
+        .await?;
+
+        Ok(row.map(|row| todo_from_row!(row)))
+    }
+
+    // Execute SELECT query on database to get todos.
+    // Todos of a group come out together, sorted within it; todo_id breaks ties.
+    pub async fn fetch_todos(&self, filter: &ProjectFilter, options: &DaylistOptions, offset: u32, limit: u32) -> Result<Vec<Todo>, DbError> {
         // All database functions must return a Result<T>
-        let rows = sqlx::query!("SELECT * FROM todo")
+        let (all, project_id) = match filter {
+            ProjectFilter::All => (true, None),
+            ProjectFilter::Inbox => (false, None), // IS NULL
+            ProjectFilter::Project(id) => (false, Some(*id)),
+        };
+        let (sort, group) = (options.sort.key(), options.group.key());
+        // Every CASE is NULL unless its mode is picked, so only the picked ones order anything.
+        // Missing due dates go last, the inbox (no project name) first.
+        let rows = sqlx::query!("SELECT * FROM todo WHERE (?1 OR project_id IS ?2) AND archived = FALSE AND deleted_at IS NULL AND NOT (?7 AND status = TRUE) ORDER BY
+CASE ?3 WHEN 'project' THEN (SELECT name FROM project WHERE project.project_id = todo.project_id) END COLLATE NOCASE,
+CASE ?3 WHEN 'priority' THEN priority END,
+CASE ?3 WHEN 'due' THEN date_due IS NULL END,
+CASE ?3 WHEN 'due' THEN substr(date_due, 1, 10) END,
+CASE ?3 WHEN 'status' THEN status END,
+CASE ?4 WHEN 'priority' THEN priority END,
+CASE ?4 WHEN 'due' THEN date_due IS NULL END,
+CASE ?4 WHEN 'due' THEN date_due END,
+CASE ?4 WHEN 'created' THEN date_created END,
+CASE ?4 WHEN 'title' THEN title END COLLATE NOCASE,
+CASE ?4 WHEN 'status' THEN status END,
+todo_id
+LIMIT ?5 OFFSET ?6",
+            all, project_id, group, sort, limit, offset, options.hide_completed)
             .fetch_all(&self.conn_pool)
         .await?;
 
         // Map the results to Todo
-        let todos: Vec<Todo> = rows.into_iter().map(|row| {
-            Todo {
-                todo_id: row.todo_id,
-                title: row.title,
-                description: row.description,
-                date_created: Db::string_to_naive_datetime(row.date_created),
-                status: row.status as i8, // Assuming status is stored as BOOLEAN (0 or 1)
-                date_due: Db::string_to_naive_datetime(row.date_due),
-                reminder_date: Db::string_to_naive_datetime(row.reminder_date),
-                parent_todo: row.parent_todo,
-                priority: row.priority,
-                project_id: row.project_id,
-            }
-        }).collect();
+        let todos: Vec<Todo> = rows.into_iter().map(|row| todo_from_row!(row)).collect();
 
         Ok(todos)
     }
 
-    pub async fn fetch_upcoming_todos(&self, offset: u32, limit: u32) -> Result<Vec<Todo>, sqlx::Error> {
-        // All database functions must return a Result<T>
-        let rows = sqlx::query!("SELECT * FROM todo WHERE date_due > CURRENT_DATE")
+    // Completed todos, archived ones included, most recently completed first
+    pub async fn fetch_completed(&self, offset: u32, limit: u32) -> Result<Vec<Todo>, DbError> {
+        let rows = sqlx::query!("SELECT * FROM todo WHERE status = TRUE AND deleted_at IS NULL
+ORDER BY date_completed IS NULL, date_completed DESC, todo_id DESC LIMIT ? OFFSET ?", limit, offset)
+            .fetch_all(&self.conn_pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| todo_from_row!(row)).collect())
+    }
+
+    // Archive todos completed before the given time, including those completed before
+    // completion times were recorded. Returns how many were archived.
+    pub async fn archive_completed(&self, before: NaiveDateTime) -> Result<u64, DbError> {
+        let ids = sqlx::query!("SELECT todo_id FROM todo
+WHERE status = TRUE AND archived = FALSE AND deleted_at IS NULL AND (date_completed IS NULL OR date_completed < ?)", before)
+            .fetch_all(&self.conn_pool)
+        .await?;
+        let ids: Vec<i64> = ids.into_iter().map(|row| row.todo_id).collect();
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let mut recording = self.start_recording(ids.clone(), vec![]).await?;
+        let ids_json = serde_json::to_string(&ids).unwrap_or_else(|_| String::from("[]"));
+        let result = sqlx::query!("UPDATE todo SET archived = TRUE WHERE todo_id IN (SELECT value FROM json_each(?))", ids_json)
+            .execute(&mut *recording.tx)
+        .await?;
+        self.finish_recording(recording, format!("Archive {} completed todos", result.rows_affected())).await?;
+        Ok(result.rows_affected())
+    }
+
+    // Open todos of a smart view, soonest due and then most important first.
+    // Dates are compared as the stored text, which sorts the same as the dates.
+    pub async fn fetch_smart_view(&self, view: SmartView, today: NaiveDate, offset: u32, limit: u32) -> Result<Vec<Todo>, DbError> {
+        let no_due_date = view == SmartView::NoDueDate;
+        let (from, to) = view.due_range(today);
+        let rows = sqlx::query!("SELECT * FROM todo WHERE status = FALSE AND deleted_at IS NULL
+AND CASE WHEN ?1 THEN date_due IS NULL ELSE date_due >= IFNULL(?2, date_due) AND date_due < IFNULL(?3, date_due || 'x') END
+ORDER BY date_due, priority, todo_id LIMIT ?4 OFFSET ?5", no_due_date, from, to, limit, offset)
+            .fetch_all(&self.conn_pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| todo_from_row!(row)).collect())
+    }
+
+    // Todos due on the given day, or with a reminder going off that day
+    pub async fn fetch_day(&self, day: NaiveDate, offset: u32, limit: u32) -> Result<Vec<Todo>, DbError> {
+        let rows = sqlx::query!("SELECT * FROM todo WHERE (substr(date_due, 1, 10) = ?1 OR substr(reminder_date, 1, 10) = ?1) AND archived = FALSE AND deleted_at IS NULL
+ORDER BY IFNULL(date_due, reminder_date) LIMIT ?2 OFFSET ?3", day, limit, offset)
             .fetch_all(&self.conn_pool)
         .await?;
 
         // Map the results to Todo
-        let todos: Vec<Todo> = rows.into_iter().map(|row| {
-            Todo {
-                todo_id: row.todo_id,
-                title: row.title,
-                description: row.description,
-                date_created: Db::string_to_naive_datetime(row.date_created),
-                status: row.status as i8, // Assuming status is stored as BOOLEAN (0 or 1)
-                date_due: Db::string_to_naive_datetime(row.date_due),
-                reminder_date: Db::string_to_naive_datetime(row.reminder_date),
-                parent_todo: row.parent_todo,
-                priority: row.priority,
-                project_id: row.project_id,
-            }
-        }).collect();
+        let todos: Vec<Todo> = rows.into_iter().map(|row| todo_from_row!(row)).collect();
 
         Ok(todos)
     }
 
-    pub async fn create_todo(&self, todo: &Todo) -> Result<(), sqlx::Error> {
+    // Open todos with a reminder in (after, until]
+    pub async fn fetch_reminders(&self, after: NaiveDateTime, until: NaiveDateTime) -> Result<Vec<Todo>, DbError> {
+        let rows = sqlx::query!("SELECT * FROM todo WHERE reminder_date > ? AND reminder_date <= ? AND status = FALSE AND deleted_at IS NULL
+ORDER BY reminder_date", after, until)
+            .fetch_all(&self.conn_pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| todo_from_row!(row)).collect())
+    }
+
+    // The first reminder of an open todo after the given time
+    pub async fn next_reminder(&self, after: NaiveDateTime) -> Result<Option<NaiveDateTime>, DbError> {
+        let record = sqlx::query!(r#"SELECT MIN(reminder_date) AS "next: String" FROM todo WHERE reminder_date > ? AND status = FALSE AND deleted_at IS NULL"#, after)
+            .fetch_one(&self.conn_pool)
+        .await?;
+        Ok(Db::string_to_naive_datetime(record.next))
+    }
+
+    pub async fn set_reminder(&self, id: i64, reminder_date: Option<NaiveDateTime>) -> Result<(), DbError> {
+        let mut recording = self.start_recording(vec![id], vec![]).await?;
+        sqlx::query!("UPDATE todo SET reminder_date = ? WHERE todo_id = ?", reminder_date, id)
+            .execute(&mut *recording.tx)
+        .await?;
+        let description = format!("Snooze \"{}\"", recording.title(id));
+        self.finish_recording(recording, description).await
+    }
+
+    // Days in [from, to) with open todos due
+    pub async fn fetch_due_days(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<NaiveDate>, DbError> {
+        let rows = sqlx::query!(r#"SELECT DISTINCT substr(date_due, 1, 10) AS "day!: String" FROM todo
+WHERE date_due >= ? AND date_due < ? AND status = FALSE AND deleted_at IS NULL"#, from, to)
+            .fetch_all(&self.conn_pool)
+        .await?;
+
+        Ok(rows.into_iter()
+            .filter_map(|row| NaiveDate::parse_from_str(&row.day, "%Y-%m-%d").ok())
+            .collect())
+    }
+
+    // Returns the id of the new todo
+    pub async fn create_todo(&self, todo: &Todo) -> Result<i64, DbError> {
+        let mut recording = self.start_recording(vec![], vec![]).await?;
+        let id = Db::insert_todo(&mut recording.tx, todo).await?;
+        self.finish_recording(recording, format!("Add \"{}\"", todo.title)).await?;
+        Ok(id)
+    }
+
+    // A todo in a project that doesn't exist yet, both added in one undo step
+    pub async fn create_todo_in_new_project(&self, todo: &Todo, project: &str) -> Result<i64, DbError> {
+        let mut recording = self.start_recording(vec![], vec![]).await?;
+        let project_id = Db::insert_project(&mut recording.tx, project).await?;
+        let todo = Todo { project_id: Some(project_id), ..todo.clone() };
+        let id = Db::insert_todo(&mut recording.tx, &todo).await?;
+        self.finish_recording(recording, format!("Add \"{}\"", todo.title)).await?;
+        Ok(id)
+    }
+
+    // create_todo without an undo step of its own, for todos created as part of another action
+    async fn insert_todo(conn: &mut SqliteConnection, todo: &Todo) -> Result<i64, DbError> {
         let current_date = Some(Local::now().naive_local());
 
-        sqlx::query!("INSERT INTO todo (title, description, date_created, status, date_due, reminder_date, parent_todo, priority, project_id) 
-VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?);",
+        let recurrence = todo.recurrence.as_ref().map(|rule| rule.to_string());
+
+        let result = sqlx::query!("INSERT INTO todo (title, description, date_created, status, date_due, reminder_date, parent_todo, priority, project_id, recurrence) 
+VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, ?);",
             todo.title, todo.description, current_date, todo.status, todo.date_due, 
             todo.reminder_date, todo.parent_todo, 
-            todo.priority, todo.project_id
+            todo.priority, todo.project_id, recurrence
         )
-            .execute(&self.conn_pool)
+            .execute(&mut *conn)
         .await?;
-        Ok(())
+        Ok(result.last_insert_rowid())
     }
 
-    pub async fn toggle_todo_status(&self, id: Option<i64>) -> Result<(), sqlx::Error> {
+    // Returns the new status, None when there is no such todo
+    pub async fn toggle_todo_status(&self, id: i64) -> Result<Option<bool>, DbError> {
         // read todo status
         // set todo status to !status
-        match id {
-            Some(id) => {
-                let record = sqlx::query!("SELECT status FROM todo WHERE todo_id = ?", id)
-                    .fetch_optional(&self.conn_pool)
-                .await?;
-
-                if let Some(value) = record {
-                    let mut status = value.status;
-                    status = !status;
-                    sqlx::query!("UPDATE todo SET status = ? WHERE todo_id = ?", status, id)
-                        .execute(&self.conn_pool)
-                    .await?;
-                }
-            }
-            None =>  utils::alert("No valid todo item selected."),// TODO error popup "no"
-        };
-        Ok(())
+        // Both in the same transaction, so a change made elsewhere in between can't be toggled over
+        let mut recording = self.start_recording(vec![id], vec![]).await?;
+        let record = sqlx::query!("SELECT status FROM todo WHERE todo_id = ?", id)
+            .fetch_optional(&mut *recording.tx)
+        .await?;
+
+        let Some(value) = record else { return Ok(None) }; // dropping the recording rolls it back
+        let mut status = value.status;
+        status = !status;
+        let date_completed = status.then(|| Local::now().naive_local());
+        // Reopening an archived todo brings it back to the active lists
+        sqlx::query!("UPDATE todo SET status = ?, date_completed = ?, archived = FALSE WHERE todo_id = ?", status, date_completed, id)
+            .execute(&mut *recording.tx)
+        .await?;
+        if status {
+            Db::schedule_next_occurrence(&mut recording.tx, id).await?;
+        }
+        let verb = if status { "Complete" } else { "Reopen" };
+        let description = format!("{} \"{}\"", verb, recording.title(id));
+        self.finish_recording(recording, description).await?;
+        Ok(Some(status))
     }
 
-    pub async fn delete_todo(&self, id: Option<i64>) -> Result<(), sqlx::Error> {
-        match id {
-            Some(id) => {
-                sqlx::query!("DELETE FROM todo WHERE todo_id = ?", id)
-                    .execute(&self.conn_pool)
-                .await?;
-            }
-            None => utils::alert("No valid todo item selected."),// TODO error popup "no"
+    // Open todos anywhere below the given one
+    pub async fn count_open_subtasks(&self, id: i64) -> Result<i64, DbError> {
+        let record = sqlx::query!(r#"WITH RECURSIVE subtree(todo_id) AS (
+    SELECT todo_id FROM todo WHERE parent_todo = ?
+    UNION SELECT todo.todo_id FROM todo JOIN subtree ON todo.parent_todo = subtree.todo_id
+)
+SELECT COUNT(*) AS "open!: i64" FROM todo WHERE todo_id IN subtree AND status = FALSE AND deleted_at IS NULL"#, id)
+            .fetch_one(&self.conn_pool)
+        .await?;
+        Ok(record.open)
+    }
+
+    pub async fn complete_subtasks(&self, id: i64) -> Result<(), DbError> {
+        let mut recording = self.start_recording(self.subtree_ids(id).await?, vec![]).await?;
+
+        // Recurring subtasks get their next occurrence like any other completion
+        let recurring = sqlx::query!("WITH RECURSIVE subtree(todo_id) AS (
+    SELECT todo_id FROM todo WHERE parent_todo = ?
+    UNION SELECT todo.todo_id FROM todo JOIN subtree ON todo.parent_todo = subtree.todo_id
+)
+SELECT todo_id FROM todo WHERE todo_id IN subtree AND status = FALSE AND deleted_at IS NULL AND recurrence IS NOT NULL", id)
+            .fetch_all(&mut *recording.tx)
+        .await?;
+
+        let now = Local::now().naive_local();
+        sqlx::query!("WITH RECURSIVE subtree(todo_id) AS (
+    SELECT todo_id FROM todo WHERE parent_todo = ?1
+    UNION SELECT todo.todo_id FROM todo JOIN subtree ON todo.parent_todo = subtree.todo_id
+)
+UPDATE todo SET status = TRUE, date_completed = ?2 WHERE todo_id IN subtree AND status = FALSE AND deleted_at IS NULL", id, now)
+            .execute(&mut *recording.tx)
+        .await?;
+
+        for row in recurring {
+            Db::schedule_next_occurrence(&mut recording.tx, row.todo_id).await?;
+        }
+        let description = format!("Complete the subtasks of \"{}\"", recording.title(id));
+        self.finish_recording(recording, description).await
+    }
+
+    pub async fn complete_todo(&self, id: i64) -> Result<(), DbError> {
+        let mut recording = self.start_recording(vec![id], vec![]).await?;
+        let now = Local::now().naive_local();
+        let result = sqlx::query!("UPDATE todo SET status = TRUE, date_completed = ? WHERE todo_id = ? AND status = FALSE", now, id)
+            .execute(&mut *recording.tx)
+        .await?;
+        if result.rows_affected() > 0 {
+            Db::schedule_next_occurrence(&mut recording.tx, id).await?;
+        }
+        let description = format!("Complete \"{}\"", recording.title(id));
+        self.finish_recording(recording, description).await
+    }
+
+    // Create the next open occurrence of a recurring todo that was just completed, with the
+    // due and reminder dates shifted by the same amount
+    async fn schedule_next_occurrence(conn: &mut SqliteConnection, id: i64) -> Result<(), DbError> {
+        let row = sqlx::query!("SELECT * FROM todo WHERE todo_id = ?", id)
+            .fetch_optional(&mut *conn)
+        .await?;
+        let todo = match row {
+            Some(row) => todo_from_row!(row),
+            None => return Ok(()),
+        };
+        let rule = match &todo.recurrence {
+            Some(rule) => rule,
+            None => return Ok(()),
         };
+
+        let now = Local::now().naive_local();
+        let anchor = todo.date_due.or(todo.reminder_date).unwrap_or(now);
+        let next = rule.next_due(anchor, now);
+        let shift = next - anchor;
+
+        let next_todo = Todo {
+            todo_id: 0,
+            status: 0,
+            date_created: None,
+            date_due: match (todo.date_due, todo.reminder_date) {
+                (Some(due), _) => Some(due + shift),
+                (None, Some(_)) => None,
+                (None, None) => Some(next), // a rule without dates still needs a day to repeat on
+            },
+            reminder_date: todo.reminder_date.map(|reminder| reminder + shift),
+            date_completed: None,
+            archived: false,
+            deleted_at: None,
+            ..todo
+        };
+
+        // Completing, reopening and completing again shouldn't stack up copies
+        let recurrence = next_todo.recurrence.as_ref().map(|rule| rule.to_string());
+        let existing = sqlx::query!("SELECT todo_id FROM todo
+WHERE status = FALSE AND deleted_at IS NULL AND title = ? AND recurrence IS ? AND date_due IS ? AND reminder_date IS ?",
+            next_todo.title, recurrence, next_todo.date_due, next_todo.reminder_date)
+            .fetch_optional(&mut *conn)
+        .await?;
+
+        if existing.is_none() {
+            Db::insert_todo(conn, &next_todo).await?;
+        }
         Ok(())
     }
 
-    pub async fn update_todo(&self, todo: &Todo) -> Result<(), sqlx::Error>{
-        sqlx::query!("UPDATE todo SET title = ?, description = ? WHERE todo_id = ?", todo.title, todo.description, todo.todo_id)
-            .execute(&self.conn_pool)
+    // The parent of the given todo, if it is still open (and not in the trash) while all of its subtasks are done
+    pub async fn parent_ready_to_complete(&self, id: i64) -> Result<Option<Todo>, DbError> {
+        let row = sqlx::query!("SELECT parent.* FROM todo AS child
+JOIN todo AS parent ON parent.todo_id = child.parent_todo
+WHERE child.todo_id = ? AND parent.status = FALSE AND parent.deleted_at IS NULL
+AND NOT EXISTS (SELECT 1 FROM todo AS sibling WHERE sibling.parent_todo = parent.todo_id AND sibling.status = FALSE AND sibling.deleted_at IS NULL)", id)
+            .fetch_optional(&self.conn_pool)
+        .await?;
+
+        Ok(row.map(|row| todo_from_row!(row)))
+    }
+
+    // (done, total) direct subtasks for every todo that has any
+    pub async fn fetch_subtask_progress(&self) -> Result<HashMap<i64, (i64, i64)>, DbError> {
+        let rows = sqlx::query!(r#"SELECT parent_todo AS "parent_todo!: i64", SUM(status) AS "done!: i64", COUNT(*) AS "total!: i64"
+FROM todo WHERE parent_todo IS NOT NULL AND deleted_at IS NULL GROUP BY parent_todo"#)
+            .fetch_all(&self.conn_pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| (row.parent_todo, (row.done, row.total))).collect())
+    }
+
+    // Subtasks are deleted along with their parent, both go to the trash
+    pub async fn delete_todo(&self, id: i64) -> Result<(), DbError> {
+        let mut recording = self.start_recording(self.subtree_ids(id).await?, vec![]).await?;
+        let now = Local::now().naive_local();
+        sqlx::query!("WITH RECURSIVE subtree(todo_id) AS (
+    SELECT ?1
+    UNION SELECT todo.todo_id FROM todo JOIN subtree ON todo.parent_todo = subtree.todo_id
+)
+UPDATE todo SET deleted_at = ?2 WHERE todo_id IN subtree AND deleted_at IS NULL", id, now)
+            .execute(&mut *recording.tx)
+        .await?;
+        let description = format!("Delete \"{}\"", recording.title(id));
+        self.finish_recording(recording, description).await
+    }
+
+    // Trashed todos, most recently deleted first
+    pub async fn fetch_trash(&self, offset: u32, limit: u32) -> Result<Vec<Todo>, DbError> {
+        let rows = sqlx::query!("SELECT * FROM todo WHERE deleted_at IS NOT NULL
+ORDER BY deleted_at DESC, todo_id LIMIT ? OFFSET ?", limit, offset)
+            .fetch_all(&self.conn_pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| todo_from_row!(row)).collect())
+    }
+
+    // Brings back the todo along with the subtasks that were deleted with it
+    pub async fn restore_todo(&self, id: i64) -> Result<(), DbError> {
+        let mut recording = self.start_recording(self.subtree_ids(id).await?, vec![]).await?;
+        sqlx::query!("WITH RECURSIVE subtree(todo_id) AS (
+    SELECT ?1
+    UNION SELECT todo.todo_id FROM todo JOIN subtree ON todo.parent_todo = subtree.todo_id
+)
+UPDATE todo SET deleted_at = NULL
+WHERE todo_id IN subtree AND deleted_at = (SELECT deleted_at FROM todo WHERE todo_id = ?1)", id)
+            .execute(&mut *recording.tx)
+        .await?;
+        let description = format!("Restore \"{}\"", recording.title(id));
+        self.finish_recording(recording, description).await
+    }
+
+    // Deletes a trashed todo for good, with every trashed todo below it. Subtasks that were
+    // restored on their own are kept and lose their parent.
+    pub async fn purge_todo(&self, id: i64) -> Result<(), DbError> {
+        let mut recording = self.start_recording(self.subtree_ids(id).await?, vec![]).await?;
+        sqlx::query!("WITH RECURSIVE subtree(todo_id) AS (
+    SELECT todo_id FROM todo WHERE todo_id = ?1 AND deleted_at IS NOT NULL
+    UNION SELECT todo.todo_id FROM todo JOIN subtree ON todo.parent_todo = subtree.todo_id WHERE todo.deleted_at IS NOT NULL
+)
+UPDATE todo SET parent_todo = NULL WHERE parent_todo IN subtree AND todo_id NOT IN subtree", id)
+            .execute(&mut *recording.tx)
+        .await?;
+        sqlx::query!("WITH RECURSIVE subtree(todo_id) AS (
+    SELECT todo_id FROM todo WHERE todo_id = ?1 AND deleted_at IS NOT NULL
+    UNION SELECT todo.todo_id FROM todo JOIN subtree ON todo.parent_todo = subtree.todo_id WHERE todo.deleted_at IS NOT NULL
+)
+DELETE FROM todo WHERE todo_id IN subtree", id)
+            .execute(&mut *recording.tx)
+        .await?;
+
+        let description = format!("Delete \"{}\" for good", recording.title(id));
+        self.finish_recording(recording, description).await
+    }
+
+    // Deletes everything trashed before the given time. Returns how many todos were purged.
+    // This runs on its own at startup, so it is permanent: it leaves no undo step behind,
+    // and the undo and redo steps that touched a purged todo are dropped so they can't bring
+    // it back. The history keeps a "purged" row per todo.
+    pub async fn purge_trash(&self, before: NaiveDateTime) -> Result<u64, DbError> {
+        let mut tx = self.conn_pool.begin().await?;
+        let now = Local::now().naive_local();
+        let source = self.source.name();
+
+        // Todos that stay mustn't point at a parent that is going
+        sqlx::query!("INSERT INTO todo_history (todo_id, field, old_value, date_changed, source)
+SELECT todo_id, 'parent_todo', parent_todo, ?2, ?3 FROM todo
+WHERE parent_todo IN (SELECT todo_id FROM todo WHERE deleted_at < ?1)
+AND (deleted_at IS NULL OR deleted_at >= ?1)", before, now, source)
+            .execute(&mut *tx)
+        .await?;
+        sqlx::query!("UPDATE todo SET parent_todo = NULL
+WHERE parent_todo IN (SELECT todo_id FROM todo WHERE deleted_at < ?1)
+AND (deleted_at IS NULL OR deleted_at >= ?1)", before)
+            .execute(&mut *tx)
+        .await?;
+        sqlx::query!("INSERT INTO todo_history (todo_id, field, old_value, date_changed, source)
+SELECT todo_id, 'purged', title, ?, ? FROM todo WHERE deleted_at < ?", now, source, before)
+            .execute(&mut *tx)
+        .await?;
+        // Steps with a purged todo, or a todo as it was under one, can't be undone or redone anymore
+        sqlx::query!("DELETE FROM undo_log WHERE EXISTS (SELECT 1 FROM json_each(undo_log.changes) AS change
+WHERE json_extract(change.value, '$.Todo.id') IN (SELECT todo_id FROM todo WHERE deleted_at < ?1)
+OR json_extract(change.value, '$.Todo.before.parent_todo') IN (SELECT todo_id FROM todo WHERE deleted_at < ?1)
+OR json_extract(change.value, '$.Todo.after.parent_todo') IN (SELECT todo_id FROM todo WHERE deleted_at < ?1))", before)
+            .execute(&mut *tx)
+        .await?;
+        let result = sqlx::query!("DELETE FROM todo WHERE deleted_at < ?", before)
+            .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn move_todo(&self, id: i64, project_id: Option<i64>) -> Result<(), DbError> {
+        let mut recording = self.start_recording(vec![id], vec![]).await?;
+        sqlx::query!("UPDATE todo SET project_id = ? WHERE todo_id = ?", project_id, id)
+            .execute(&mut *recording.tx)
+        .await?;
+        let description = format!("Move \"{}\"", recording.title(id));
+        self.finish_recording(recording, description).await
+    }
+
+    pub async fn update_todo(&self, todo: &Todo) -> Result<(), DbError>{
+        // status and date_created are left alone, they aren't edited through the form
+        let mut recording = self.start_recording(vec![todo.todo_id], vec![]).await?;
+        Db::write_todo(&mut recording.tx, todo).await?;
+        let description = format!("Edit \"{}\"", recording.title(todo.todo_id));
+        self.finish_recording(recording, description).await
+    }
+
+    // An edit that puts the todo in a project that doesn't exist yet, both in one undo step
+    pub async fn update_todo_in_new_project(&self, todo: &Todo, project: &str) -> Result<(), DbError> {
+        let mut recording = self.start_recording(vec![todo.todo_id], vec![]).await?;
+        let project_id = Db::insert_project(&mut recording.tx, project).await?;
+        Db::write_todo(&mut recording.tx, &Todo { project_id: Some(project_id), ..todo.clone() }).await?;
+        let description = format!("Edit \"{}\"", recording.title(todo.todo_id));
+        self.finish_recording(recording, description).await
+    }
+
+    // update_todo without an undo step of its own
+    async fn write_todo(conn: &mut SqliteConnection, todo: &Todo) -> Result<(), DbError> {
+        let recurrence = todo.recurrence.as_ref().map(|rule| rule.to_string());
+        sqlx::query!("UPDATE todo SET title = ?, description = ?, date_due = ?, reminder_date = ?, parent_todo = ?, priority = ?, project_id = ?, recurrence = ?
+WHERE todo_id = ?",
+            todo.title, todo.description, todo.date_due, todo.reminder_date, todo.parent_todo,
+            todo.priority, todo.project_id, recurrence, todo.todo_id
+        )
+            .execute(&mut *conn)
         .await?;
         Ok(())
     }
 
 
-    fn string_to_naive_datetime(date_str: Option<String>) -> Option<NaiveDateTime> {
-        date_str.and_then(|s| NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S").ok())
+    // Execute SELECT query on database to get projects, favorites first
+    pub async fn fetch_projects(&self) -> Result<Vec<Project>, DbError> {
+        let rows = sqlx::query!("SELECT * FROM project ORDER BY favorite_status DESC, name COLLATE NOCASE")
+            .fetch_all(&self.conn_pool)
+        .await?;
+
+        // Map the results to Project
+        let projects: Vec<Project> = rows.into_iter().map(|row| {
+            Project {
+                project_id: row.project_id,
+                name: row.name,
+                description: row.description,
+                color: row.color,
+                favorite_status: row.favorite_status.unwrap_or(false), // NULL on hand-made rows
+            }
+        }).collect();
+
+        Ok(projects)
     }
 
+    // Returns the id of the new project
+    pub async fn create_project(&self, name: &str) -> Result<i64, DbError> {
+        let mut recording = self.start_recording(vec![], vec![]).await?;
+        let id = Db::insert_project(&mut recording.tx, name).await?;
+        self.finish_recording(recording, format!("Add project \"{}\"", name)).await?;
+        Ok(id)
+    }
+
+    async fn insert_project(conn: &mut SqliteConnection, name: &str) -> Result<i64, DbError> {
+        let result = sqlx::query!("INSERT INTO project (name, favorite_status) VALUES(?, FALSE);", name)
+            .execute(&mut *conn)
+        .await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    pub async fn rename_project(&self, id: i64, name: &str) -> Result<(), DbError> {
+        let mut recording = self.start_recording(vec![], vec![id]).await?;
+        sqlx::query!("UPDATE project SET name = ? WHERE project_id = ?", name, id)
+            .execute(&mut *recording.tx)
+        .await?;
+        let description = format!("Rename project \"{}\"", recording.project_name(id));
+        self.finish_recording(recording, description).await
+    }
+
+    pub async fn toggle_project_favorite(&self, id: i64) -> Result<(), DbError> {
+        let mut recording = self.start_recording(vec![], vec![id]).await?;
+        sqlx::query!("UPDATE project SET favorite_status = NOT IFNULL(favorite_status, FALSE) WHERE project_id = ?", id)
+            .execute(&mut *recording.tx)
+        .await?;
+        let description = format!("Favorite project \"{}\"", recording.project_name(id));
+        self.finish_recording(recording, description).await
+    }
+
+    // Todos in a deleted project are kept and moved back to the inbox
+    pub async fn delete_project(&self, id: i64) -> Result<(), DbError> {
+        let todo_ids = sqlx::query!("SELECT todo_id FROM todo WHERE project_id = ?", id)
+            .fetch_all(&self.conn_pool)
+        .await?;
+        let todo_ids = todo_ids.into_iter().map(|row| row.todo_id).collect();
+        let mut recording = self.start_recording(todo_ids, vec![id]).await?;
+        sqlx::query!("UPDATE todo SET project_id = NULL WHERE project_id = ?", id)
+            .execute(&mut *recording.tx)
+        .await?;
+        sqlx::query!("DELETE FROM project WHERE project_id = ?", id)
+            .execute(&mut *recording.tx)
+        .await?;
+
+        let description = format!("Delete project \"{}\"", recording.project_name(id));
+        self.finish_recording(recording, description).await
+    }
+
+
+    pub(crate) fn string_to_naive_datetime(date_str: Option<String>) -> Option<NaiveDateTime> {
+        // sqlx stores fractional seconds when there are any
+        date_str.and_then(|s| NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S%.f").ok())
+    }
+
+}
+
+// A database file in the temp folder that no other test uses, starting out empty
+#[cfg(test)]
+pub(crate) fn test_db_options(name: &str) -> SqliteConnectOptions {
+    let path = std::env::temp_dir().join(format!("day_list_test_{}_{}.db", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    SqliteConnectOptions::new().filename(path)
+}
+
+// test_db_options, migrated and opened
+#[cfg(test)]
+pub(crate) async fn test_db(name: &str) -> Db {
+    Db::new(test_db_options(name)).await.unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A database set up by running the first migrations with sqlite3
+    async fn hand_made_db(name: &str, migrations: usize) -> SqliteConnectOptions {
+        let options = test_db_options(name);
+        let conn = SqlitePool::connect_with(options.clone().create_if_missing(true)).await.unwrap();
+        for migration in MIGRATOR.iter().take(migrations) {
+            sqlx::raw_sql(&migration.sql).execute(&conn).await.unwrap();
+        }
+        conn.close().await;
+        options
+    }
+
+    async fn applied(list_db: &Db) -> usize {
+        let mut conn = list_db.conn_pool.acquire().await.unwrap();
+        conn.list_applied_migrations().await.unwrap().len()
+    }
+
+    #[tokio::test]
+    async fn opens_a_hand_made_schema() {
+        let options = hand_made_db("full", MIGRATOR.iter().count()).await;
+        let list_db = Db::new(options.clone()).await.unwrap();
+        assert_eq!(applied(&list_db).await, MIGRATOR.iter().count());
+    }
+
+    #[tokio::test]
+    async fn migrates_the_rest_of_an_older_hand_made_schema() {
+        let options = hand_made_db("partial", 2).await;
+        let list_db = Db::new(options.clone()).await.unwrap();
+        assert_eq!(applied(&list_db).await, MIGRATOR.iter().count());
+        sqlx::query("SELECT deleted_at FROM todo").fetch_all(&list_db.conn_pool).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn opens_a_new_database_twice() {
+        let options = hand_made_db("new", 0).await;
+        Db::new(options.clone()).await.unwrap().conn_pool.close().await;
+        let list_db = Db::new(options.clone()).await.unwrap();
+        assert_eq!(applied(&list_db).await, MIGRATOR.iter().count());
+    }
+
+    #[tokio::test]
+    async fn opens_a_path_with_url_characters() {
+        let path = std::env::temp_dir().join(format!("day_list_db_what?#%20_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        Db::new(SqliteConnectOptions::new().filename(&path)).await.unwrap();
+        assert!(path.exists());
+    }
+
+    #[tokio::test]
+    async fn an_action_left_unfinished_is_rolled_back() {
+        let list_db = test_db("unfinished").await;
+        let id = list_db.create_project("Home").await.unwrap();
+
+        // Failing before finish_recording drops the recording, and the change goes with it
+        let mut recording = list_db.start_recording(vec![], vec![id]).await.unwrap();
+        sqlx::query("UPDATE project SET name = 'Work' WHERE project_id = ?").bind(id)
+            .execute(&mut *recording.tx)
+        .await.unwrap();
+        drop(recording);
+
+        let projects = list_db.fetch_projects().await.unwrap();
+        assert_eq!(projects[0].name, "Home");
+        assert_eq!(list_db.undo().await.unwrap().as_deref(), Some("Add project \"Home\""));
+    }
+
+    #[tokio::test]
+    async fn completing_a_recurring_todo_schedules_one_next_occurrence() {
+        let list_db = test_db("recurring").await;
+        let at = |day: u32, hour: u32| NaiveDate::from_ymd_opt(2030, 1, day).and_then(|day| day.and_hms_opt(hour, 0, 0));
+        // Monday the 7th, so the next one is on Friday the 11th
+        let gym = Todo {
+            date_due: at(7, 18),
+            reminder_date: at(7, 17),
+            recurrence: Some("weekly:mon,fri".parse().unwrap()),
+            ..Todo::titled("Gym")
+        };
+        let id = list_db.create_todo(&gym).await.unwrap();
+
+        assert_eq!(list_db.toggle_todo_status(id).await.unwrap(), Some(true));
+        // Reopening and completing it again doesn't add another copy
+        assert_eq!(list_db.toggle_todo_status(id).await.unwrap(), Some(false));
+        list_db.complete_todo(id).await.unwrap();
+
+        let next: Vec<Todo> = list_db.search("Gym", 0, 10).await.unwrap()
+            .into_iter()
+            .filter(|todo| todo.todo_id != id)
+            .collect();
+        assert_eq!(next.len(), 1);
+        assert_eq!(next[0].status, 0);
+        assert_eq!(next[0].date_due, at(11, 18));
+        assert_eq!(next[0].reminder_date, at(11, 17));
+        assert_eq!(next[0].recurrence, Some("weekly:mon,fri".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn keeps_the_todos_of_a_database_with_only_the_first_schema() {
+        let options = hand_made_db("init_only", 1).await;
+        let conn = SqlitePool::connect_with(options.clone()).await.unwrap();
+        sqlx::query("INSERT INTO todo (title, description, date_created, status, date_due, reminder_date, parent_todo, priority, project_id)
+VALUES ('Subtask', NULL, '2025-01-02 03:04:05', FALSE, 1893456000, NULL, 2, 3, NULL), ('Parent', 'Kept', NULL, TRUE, NULL, NULL, NULL, 4, NULL)")
+            .execute(&conn)
+        .await.unwrap();
+        conn.close().await;
+
+        let list_db = Db::new(options.clone()).await.unwrap();
+        assert_eq!(applied(&list_db).await, MIGRATOR.iter().count());
+        let subtask = list_db.fetch_todo(1).await.unwrap().unwrap();
+        assert_eq!(subtask.title, "Subtask");
+        assert_eq!(subtask.parent_todo, Some(2));
+        assert_eq!(subtask.date_created, NaiveDate::from_ymd_opt(2025, 1, 2).and_then(|day| day.and_hms_opt(3, 4, 5)));
+        assert_eq!(subtask.date_due, NaiveDate::from_ymd_opt(2030, 1, 1).and_then(|day| day.and_hms_opt(0, 0, 0)));
+        let parent = list_db.fetch_todo(2).await.unwrap().unwrap();
+        assert_eq!((parent.description.as_deref(), parent.status), (Some("Kept"), 1));
+    }
+
+    #[tokio::test]
+    async fn a_trashed_parent_isnt_offered_for_completion() {
+        let list_db = test_db("trashed_parent").await;
+        let parent = list_db.create_todo(&Todo::titled("Move out")).await.unwrap();
+        let subtask = list_db.create_todo(&Todo { parent_todo: Some(parent), ..Todo::titled("Pack") }).await.unwrap();
+
+        list_db.toggle_todo_status(subtask).await.unwrap();
+        assert!(list_db.parent_ready_to_complete(subtask).await.unwrap().is_some());
+
+        list_db.delete_todo(parent).await.unwrap();
+        list_db.restore_todo(subtask).await.unwrap();
+        assert!(list_db.parent_ready_to_complete(subtask).await.unwrap().is_none());
+        assert_eq!(list_db.toggle_todo_status(parent + 100).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn undo_cant_bring_back_a_purged_todo() {
+        let list_db = test_db("purged_undo").await;
+        list_db.create_project("Home").await.unwrap();
+        let id = list_db.create_todo(&Todo::titled("Old")).await.unwrap();
+        list_db.delete_todo(id).await.unwrap();
+
+        let later = Local::now().naive_local() + chrono::Duration::seconds(1);
+        assert_eq!(list_db.purge_trash(later).await.unwrap(), 1);
+
+        // The delete and the add go, the steps before them stay
+        assert_eq!(list_db.undo().await.unwrap().as_deref(), Some("Add project \"Home\""));
+        assert!(list_db.fetch_todo(id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn purging_a_parent_leaves_undo_working_for_its_restored_subtask() {
+        let list_db = test_db("purged_parent").await;
+        list_db.create_project("Home").await.unwrap();
+        let parent = list_db.create_todo(&Todo::titled("Move out")).await.unwrap();
+        let subtask = list_db.create_todo(&Todo { parent_todo: Some(parent), ..Todo::titled("Pack") }).await.unwrap();
+        list_db.delete_todo(parent).await.unwrap();
+        list_db.restore_todo(subtask).await.unwrap();
+
+        let later = Local::now().naive_local() + chrono::Duration::seconds(1);
+        assert_eq!(list_db.purge_trash(later).await.unwrap(), 1);
+        assert_eq!(list_db.fetch_todo(subtask).await.unwrap().unwrap().parent_todo, None);
+        let history = list_db.fetch_history(subtask).await.unwrap();
+        assert_eq!((history[0].field.as_str(), history[0].old_value.clone()), ("parent_todo", Some(parent.to_string())));
+
+        // Every step that had the subtask under the purged parent is gone with it
+        assert_eq!(list_db.undo().await.unwrap().as_deref(), Some("Add project \"Home\""));
+        assert!(list_db.fetch_todo(subtask).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn a_todo_and_its_new_project_are_undone_together() {
+        let list_db = test_db("new_project").await;
+        let id = list_db.create_todo_in_new_project(&Todo::titled("Report"), "Work").await.unwrap();
+        let projects = list_db.fetch_projects().await.unwrap();
+        assert_eq!(list_db.fetch_todo(id).await.unwrap().unwrap().project_id, Some(projects[0].project_id));
+
+        assert_eq!(list_db.undo().await.unwrap().as_deref(), Some("Add \"Report\""));
+        assert!(list_db.fetch_todo(id).await.unwrap().is_none());
+        assert!(list_db.fetch_projects().await.unwrap().is_empty());
+        assert_eq!(list_db.undo().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn an_edit_and_its_new_project_are_undone_together() {
+        let list_db = test_db("edit_new_project").await;
+        let id = list_db.create_todo(&Todo::titled("Report")).await.unwrap();
+        let todo = list_db.fetch_todo(id).await.unwrap().unwrap();
+        list_db.update_todo_in_new_project(&todo, "Work").await.unwrap();
+        assert!(list_db.fetch_todo(id).await.unwrap().unwrap().project_id.is_some());
+
+        assert_eq!(list_db.undo().await.unwrap().as_deref(), Some("Edit \"Report\""));
+        assert_eq!(list_db.fetch_todo(id).await.unwrap().unwrap().project_id, None);
+        assert!(list_db.fetch_projects().await.unwrap().is_empty());
+    }
 }
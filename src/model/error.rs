@@ -0,0 +1,44 @@
+use std::fmt;
+
+// Errors from the model layer
+#[derive(Debug)]
+pub enum DbError {
+    Sqlx(sqlx::Error),
+    Undo(serde_json::Error), // an undo step couldn't be stored or read back
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbError::Sqlx(e) => write!(f, "{}", e),
+            DbError::Undo(e) => write!(f, "unreadable undo step: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for DbError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DbError::Sqlx(e) => Some(e),
+            DbError::Undo(e) => Some(e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for DbError {
+    fn from(e: sqlx::Error) -> Self {
+        DbError::Sqlx(e)
+    }
+}
+
+impl From<sqlx::migrate::MigrateError> for DbError {
+    fn from(e: sqlx::migrate::MigrateError) -> Self {
+        DbError::Sqlx(e.into())
+    }
+}
+
+impl From<serde_json::Error> for DbError {
+    fn from(e: serde_json::Error) -> Self {
+        DbError::Undo(e)
+    }
+}
@@ -0,0 +1,203 @@
+use chrono::NaiveDateTime;
+use serde_json::Value;
+use sqlx::SqliteConnection;
+
+use crate::model::db::Db;
+use crate::model::schema::Todo;
+use crate::model::error::DbError;
+
+// Where a change to a todo came from
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Source {
+    Tui,
+    Cli,
+}
+
+impl Source {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Source::Tui => "tui",
+            Source::Cli => "cli",
+        }
+    }
+}
+
+// One field of a todo changing. Creating a todo is a single "created" row,
+// deleting it for good a single "purged" row, both with the title as value.
+// Undo and redo are an "undone" or "redone" row with the action as value.
+pub struct HistoryEntry {
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub date_changed: Option<NaiveDateTime>,
+    pub source: String,
+}
+
+impl HistoryEntry {
+    pub fn format(&self, date_format: &str) -> String {
+        let date_changed = self.date_changed
+            .map(|date| date.format(date_format).to_string())
+            .unwrap_or_default();
+        let change = match self.field.as_str() {
+            "created" | "purged" => self.field.clone(),
+            "undone" | "redone" => format!("{} {}", self.field, self.new_value.as_deref().unwrap_or_default()),
+            field => format!("{}: {} → {}",
+                field_name(field),
+                format_value(field, self.old_value.as_deref(), date_format),
+                format_value(field, self.new_value.as_deref(), date_format),
+            ),
+        };
+        format!("{}  {:<6} {}", date_changed, self.source, change)
+    }
+}
+
+impl Db {
+    // Every change to the todo, newest first
+    pub async fn fetch_history(&self, id: i64) -> Result<Vec<HistoryEntry>, DbError> {
+        let rows = sqlx::query!("SELECT * FROM todo_history WHERE todo_id = ? ORDER BY history_id DESC", id)
+            .fetch_all(&self.conn_pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| HistoryEntry {
+            field: row.field,
+            old_value: row.old_value,
+            new_value: row.new_value,
+            date_changed: Db::string_to_naive_datetime(Some(row.date_changed)),
+            source: row.source,
+        }).collect())
+    }
+}
+
+// Write a row for every field that differs between the two versions of a todo
+pub(crate) async fn record_history(conn: &mut SqliteConnection, source: Source, date_changed: NaiveDateTime,
+    before: Option<&Todo>, after: Option<&Todo>) -> Result<(), DbError> {
+    let (id, changes) = match (before, after) {
+        (None, Some(todo)) => (todo.todo_id, vec![(String::from("created"), None, Some(todo.title.clone()))]),
+        (Some(todo), None) => (todo.todo_id, vec![(String::from("purged"), Some(todo.title.clone()), None)]),
+        (Some(before), Some(after)) => (after.todo_id, changed_fields(before, after)),
+        (None, None) => return Ok(()),
+    };
+
+    let source = source.name();
+    for (field, old_value, new_value) in changes {
+        sqlx::query!("INSERT INTO todo_history (todo_id, field, old_value, new_value, date_changed, source) VALUES (?, ?, ?, ?, ?, ?)",
+            id, field, old_value, new_value, date_changed, source)
+            .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+// An undo or redo touching the todo, logged as that rather than as the fields it put back
+pub(crate) async fn record_undo(conn: &mut SqliteConnection, source: Source, date_changed: NaiveDateTime,
+    id: i64, redo: bool, description: &str) -> Result<(), DbError> {
+    let field = if redo { "redone" } else { "undone" };
+    let source = source.name();
+    sqlx::query!("INSERT INTO todo_history (todo_id, field, new_value, date_changed, source) VALUES (?, ?, ?, ?, ?)",
+        id, field, description, date_changed, source)
+        .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+// Field name, old and new value of every field that changed, compared as the text they are logged as
+fn changed_fields(before: &Todo, after: &Todo) -> Vec<(String, Option<String>, Option<String>)> {
+    let (Ok(Value::Object(before)), Ok(Value::Object(after))) = (serde_json::to_value(before), serde_json::to_value(after)) else {
+        return vec![];
+    };
+
+    after.into_iter()
+        .filter(|(field, _)| field != "todo_id")
+        .map(|(field, value)| {
+            let old_value = before.get(&field).and_then(value_text);
+            (field, old_value, value_text(&value))
+        })
+        .filter(|(_, old_value, new_value)| old_value != new_value)
+        .collect()
+}
+
+// An empty text is no value, older versions saved empty descriptions that way
+fn value_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(text) if text.is_empty() => None,
+        Value::String(text) => Some(text.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+fn field_name(field: &str) -> &str {
+    match field {
+        "date_due" => "due",
+        "reminder_date" => "reminder",
+        "parent_todo" => "parent",
+        "project_id" => "project",
+        "recurrence" => "repeat",
+        "date_completed" => "completed",
+        "deleted_at" => "trashed",
+        field => field,
+    }
+}
+
+// Dates are kept the way serde writes them, show them like everywhere else
+fn format_value(field: &str, value: Option<&str>, date_format: &str) -> String {
+    match (field, value) {
+        (_, None) => String::from("--"),
+        ("status", Some("1")) => String::from("done"),
+        ("status", Some(_)) => String::from("open"),
+        (_, Some(value)) => match NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f") {
+            Ok(date) => date.format(date_format).to_string(),
+            Err(_) => value.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::controller::edit_todo::EditTodo;
+    use crate::model::db::test_db;
+
+    fn todo(description: Option<&str>) -> Todo {
+        Todo {
+            description: description.map(String::from),
+            reminder_date: NaiveDate::from_ymd_opt(2026, 11, 2).and_then(|day| day.and_hms_opt(14, 0, 30)),
+            ..Todo::titled("Call the bank")
+        }
+    }
+
+    #[tokio::test]
+    async fn saving_an_untouched_todo_records_no_change() {
+        let list_db = test_db("history_untouched").await;
+        let id = list_db.create_todo(&todo(None)).await.unwrap();
+
+        let mut edit = EditTodo::new();
+        edit.load(&list_db.fetch_todo(id).await.unwrap().unwrap(), String::new());
+        list_db.update_todo(&edit.update_todo(None)).await.unwrap();
+
+        let fields: Vec<String> = list_db.fetch_history(id).await.unwrap().into_iter().map(|entry| entry.field).collect();
+        assert_eq!(fields, ["created"]);
+    }
+
+    #[tokio::test]
+    async fn undo_and_redo_are_logged_as_such() {
+        let list_db = test_db("history_undo").await;
+        let id = list_db.create_todo(&todo(None)).await.unwrap();
+        list_db.undo().await.unwrap();
+        list_db.redo().await.unwrap();
+
+        let history = list_db.fetch_history(id).await.unwrap();
+        let fields: Vec<&str> = history.iter().map(|entry| entry.field.as_str()).collect();
+        assert_eq!(fields, ["redone", "undone", "created"]);
+        assert_eq!(history[1].new_value.as_deref(), Some("Add \"Call the bank\""));
+    }
+
+    #[test]
+    fn an_empty_description_is_the_same_as_none() {
+        assert!(changed_fields(&todo(Some("")), &todo(None)).is_empty());
+        let changes = changed_fields(&todo(None), &todo(Some("Before noon")));
+        assert_eq!(changes, [(String::from("description"), None, Some(String::from("Before noon")))]);
+    }
+}
@@ -1,2 +1,7 @@
 pub mod schema;
 pub mod db;
+pub mod recurrence;
+pub mod date_input;
+pub mod undo;
+pub mod history;
+pub mod error;
@@ -0,0 +1,214 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveDateTime, Weekday};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// How a todo repeats, stored as text in todo.recurrence
+#[derive(Clone, PartialEq, Debug)]
+pub enum Recurrence {
+    Daily,                 // daily
+    Weekly(Vec<Weekday>),  // weekly:mon,fri, or weekly for every 7 days
+    Monthly(u32),          // monthly:15, clamped to the last day of short months
+    AfterCompletion(u64),  // after:3, N days after it was completed
+}
+
+pub const RECURRENCE_HELP: &str = "daily | weekly:mon,fri | monthly:15 | after:3";
+
+impl Recurrence {
+    // When the next occurrence is due. `due` is the date the completed occurrence was due,
+    // occurrences that were missed while it was overdue are skipped rather than piled up.
+    pub fn next_due(&self, due: NaiveDateTime, completed: NaiveDateTime) -> NaiveDateTime {
+        let time = due.time();
+        let mut day = due.date();
+
+        if let Recurrence::AfterCompletion(days) = self {
+            return completed.date().checked_add_days(Days::new(*days)).unwrap_or(day).and_time(time);
+        }
+
+        loop {
+            let next = match self.step(day) {
+                Some(next) => next,
+                None => return due, // only past the end of chrono's calendar
+            };
+            day = next;
+            if day > completed.date() {
+                return day.and_time(time);
+            }
+        }
+    }
+
+    // The occurrence after `day`
+    fn step(&self, day: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Recurrence::Daily => day.succ_opt(),
+            Recurrence::Weekly(weekdays) if weekdays.is_empty() => day.checked_add_days(Days::new(7)),
+            Recurrence::Weekly(weekdays) => {
+                let mut next = day.succ_opt()?;
+                while !weekdays.contains(&next.weekday()) {
+                    next = next.succ_opt()?;
+                }
+                Some(next)
+            }
+            Recurrence::Monthly(day_of_month) => {
+                let month = day.with_day(1)?;
+                // Still ahead this month (only when the day was clamped the month before)
+                let this_month = clamp_day(month, *day_of_month)?;
+                if this_month > day {
+                    return Some(this_month);
+                }
+                clamp_day(month.checked_add_months(Months::new(1))?, *day_of_month)
+            }
+            Recurrence::AfterCompletion(days) => day.checked_add_days(Days::new(*days)),
+        }
+    }
+}
+
+// Day `day` of the month starting at `month`, or its last day when the month is shorter
+fn clamp_day(month: NaiveDate, day: u32) -> Option<NaiveDate> {
+    let last = month.checked_add_months(Months::new(1))?.pred_opt()?.day();
+    month.with_day(day.min(last))
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly(weekdays) if weekdays.is_empty() => write!(f, "weekly"),
+            Recurrence::Weekly(weekdays) => {
+                let names = weekdays.iter()
+                    .map(|weekday| weekday.to_string().to_lowercase())
+                    .collect::<Vec<String>>();
+                write!(f, "weekly:{}", names.join(","))
+            }
+            Recurrence::Monthly(day) => write!(f, "monthly:{}", day),
+            Recurrence::AfterCompletion(days) => write!(f, "after:{}", days),
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let (kind, arg) = match s.split_once(':') {
+            Some((kind, arg)) => (kind.trim(), Some(arg.trim())),
+            None => (s.as_str(), None),
+        };
+
+        let invalid = || format!("'{}' is not a repeat rule ({})", s, RECURRENCE_HELP);
+        match (kind, arg) {
+            ("daily", None) => Ok(Recurrence::Daily),
+            // "weekly:" is how a plain weekly rule used to be written
+            ("weekly", None | Some("")) => Ok(Recurrence::Weekly(vec![])),
+            ("weekly", Some(days)) => {
+                let mut weekdays = days.split(',')
+                    .map(|day| day.trim().parse::<Weekday>().map_err(|_| invalid()))
+                    .collect::<Result<Vec<Weekday>, String>>()?;
+                weekdays.sort_by_key(|weekday| weekday.num_days_from_monday());
+                weekdays.dedup();
+                Ok(Recurrence::Weekly(weekdays))
+            }
+            ("monthly", Some(day)) => match day.parse() {
+                Ok(day @ 1..=31) => Ok(Recurrence::Monthly(day)),
+                _ => Err(invalid()),
+            },
+            ("after", Some(days)) => match days.parse() {
+                Ok(days) if days > 0 => Ok(Recurrence::AfterCompletion(days)),
+                _ => Err(invalid()),
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
+// Same text as in the database
+impl Serialize for Recurrence {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Recurrence {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rule = String::deserialize(deserializer)?;
+        rule.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap().and_hms_opt(9, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let rules = [
+            Recurrence::Daily,
+            Recurrence::Weekly(vec![]),
+            Recurrence::Weekly(vec![Weekday::Mon]),
+            Recurrence::Weekly(vec![Weekday::Mon, Weekday::Wed, Weekday::Fri]),
+            Recurrence::Monthly(1),
+            Recurrence::Monthly(31),
+            Recurrence::AfterCompletion(3),
+        ];
+        for rule in rules {
+            assert_eq!(rule.to_string().parse::<Recurrence>(), Ok(rule.clone()), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("weekly".parse(), Ok(Recurrence::Weekly(vec![])));
+        assert_eq!("weekly:".parse(), Ok(Recurrence::Weekly(vec![])));
+        assert_eq!(Recurrence::Weekly(vec![]).to_string(), "weekly");
+        // sorted and deduplicated
+        assert_eq!(" Weekly: fri, mon,fri ".parse(), Ok(Recurrence::Weekly(vec![Weekday::Mon, Weekday::Fri])));
+        assert_eq!("monthly:15".parse(), Ok(Recurrence::Monthly(15)));
+        assert!("monthly:32".parse::<Recurrence>().is_err());
+        assert!("monthly".parse::<Recurrence>().is_err());
+        assert!("after:0".parse::<Recurrence>().is_err());
+        assert!("weekly:someday".parse::<Recurrence>().is_err());
+        assert!("hourly".parse::<Recurrence>().is_err());
+    }
+
+    #[test]
+    fn next_due_daily() {
+        let rule = Recurrence::Daily;
+        assert_eq!(rule.next_due(at(2026, 10, 14), at(2026, 10, 14)), at(2026, 10, 15));
+        // missed days are skipped, not piled up
+        assert_eq!(rule.next_due(at(2026, 10, 10), at(2026, 10, 14)), at(2026, 10, 15));
+        // completed early, the next one is still the day after it was due
+        assert_eq!(rule.next_due(at(2026, 10, 20), at(2026, 10, 14)), at(2026, 10, 21));
+    }
+
+    #[test]
+    fn next_due_weekly() {
+        // 2026-10-14 is a Wednesday
+        assert_eq!(Recurrence::Weekly(vec![]).next_due(at(2026, 10, 14), at(2026, 10, 14)), at(2026, 10, 21));
+        let rule = Recurrence::Weekly(vec![Weekday::Mon, Weekday::Fri]);
+        assert_eq!(rule.next_due(at(2026, 10, 14), at(2026, 10, 14)), at(2026, 10, 16));
+        assert_eq!(rule.next_due(at(2026, 10, 16), at(2026, 10, 16)), at(2026, 10, 19));
+        assert_eq!(rule.next_due(at(2026, 10, 16), at(2026, 10, 27)), at(2026, 10, 30));
+    }
+
+    #[test]
+    fn next_due_monthly() {
+        let rule = Recurrence::Monthly(31);
+        assert_eq!(rule.next_due(at(2026, 1, 31), at(2026, 1, 31)), at(2026, 2, 28));
+        // back to the 31st after a short month
+        assert_eq!(rule.next_due(at(2026, 2, 28), at(2026, 2, 28)), at(2026, 3, 31));
+        assert_eq!(rule.next_due(at(2026, 12, 31), at(2026, 12, 31)), at(2027, 1, 31));
+        assert_eq!(Recurrence::Monthly(15).next_due(at(2026, 10, 15), at(2026, 12, 1)), at(2026, 12, 15));
+    }
+
+    #[test]
+    fn next_due_after_completion() {
+        let rule = Recurrence::AfterCompletion(3);
+        assert_eq!(rule.next_due(at(2026, 10, 1), at(2026, 10, 14)), at(2026, 10, 17));
+    }
+}
@@ -1,6 +1,10 @@
 use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
 
-#[derive(sqlx::FromRow)]
+use crate::model::recurrence::Recurrence;
+
+// Rows are mapped by hand in model::db, the TEXT columns need converting
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Todo {
     pub todo_id: i64,
     pub title: String,
@@ -12,9 +16,36 @@ pub struct Todo {
     pub parent_todo: Option<i64>,
     pub priority: i64,
     pub project_id: Option<i64>,
+    pub recurrence: Option<Recurrence>,
+    pub date_completed: Option<NaiveDateTime>,
+    pub archived: bool, // completed and moved out of the active lists, still searchable
+    pub deleted_at: Option<NaiveDateTime>, // in the trash since
+}
+
+// A new todo with nothing but a title, tests set the fields they care about on top of it
+#[cfg(test)]
+impl Todo {
+    pub fn titled(title: &str) -> Todo {
+        Todo {
+            todo_id: 0,
+            title: title.to_string(),
+            description: None,
+            date_created: None,
+            status: 0,
+            date_due: None,
+            reminder_date: None,
+            parent_todo: None,
+            priority: 4,
+            project_id: None,
+            recurrence: None,
+            date_completed: None,
+            archived: false,
+            deleted_at: None,
+        }
+    }
 }
 
-#[derive(sqlx::FromRow)]
+#[derive(sqlx::FromRow, Serialize, Deserialize)]
 pub struct Project {
     pub project_id: i64,
     pub name: String,
@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use sqlx::{Sqlite, SqliteConnection, Transaction};
+
+use crate::model::db::{todo_from_row, Db};
+use crate::model::history::{record_history, record_undo};
+use crate::model::error::DbError;
+use crate::model::schema::{Project, Todo};
+
+// Undo steps kept, the oldest are dropped beyond this
+const UNDO_LIMIT: i64 = 200;
+
+// A row before and after an action, None where it didn't exist
+#[derive(Serialize, Deserialize)]
+enum Change {
+    Todo { id: i64, before: Option<Todo>, after: Option<Todo> },
+    Project { id: i64, before: Option<Project>, after: Option<Project> },
+}
+
+// The rows an action is going to touch, as they were before it ran.
+// Rows the action creates are picked up afterwards by their new ids.
+pub struct Recording {
+    pub tx: Transaction<'static, Sqlite>, // the action runs in here, it commits along with its undo step
+    todo_ids: Vec<i64>,
+    project_ids: Vec<i64>,
+    todos: HashMap<i64, Todo>,
+    projects: HashMap<i64, Project>,
+    last_todo_id: i64,
+    last_project_id: i64,
+}
+
+impl Recording {
+    // For describing the action, the title before it ran
+    pub fn title(&self, id: i64) -> String {
+        self.todos.get(&id).map(|todo| todo.title.clone()).unwrap_or_default()
+    }
+
+    pub fn project_name(&self, id: i64) -> String {
+        self.projects.get(&id).map(|project| project.name.clone()).unwrap_or_default()
+    }
+}
+
+impl Db {
+    // Snapshot the rows an action may change and start its transaction. The action runs its
+    // statements on recording.tx, then calls finish_recording to store the undo step and commit.
+    pub async fn start_recording(&self, todo_ids: Vec<i64>, project_ids: Vec<i64>) -> Result<Recording, DbError> {
+        let mut tx = self.conn_pool.begin().await?;
+        let last = sqlx::query!(r#"SELECT (SELECT IFNULL(MAX(todo_id), 0) FROM todo) AS "todo!: i64",
+(SELECT IFNULL(MAX(project_id), 0) FROM project) AS "project!: i64""#)
+            .fetch_one(&mut *tx)
+        .await?;
+
+        let todos = fetch_todo_rows(&mut tx, &todo_ids, last.todo).await?;
+        let projects = fetch_project_rows(&mut tx, &project_ids, last.project).await?;
+        Ok(Recording {
+            tx,
+            todo_ids,
+            project_ids,
+            todos: todos.into_iter().map(|todo| (todo.todo_id, todo)).collect(),
+            projects: projects.into_iter().map(|project| (project.project_id, project)).collect(),
+            last_todo_id: last.todo,
+            last_project_id: last.project,
+        })
+    }
+
+    // Store what the action changed as one undo step and commit it together with the action.
+    // Anything that was undone can't be redone after this.
+    pub async fn finish_recording(&self, mut recording: Recording, description: String) -> Result<(), DbError> {
+        let mut tx = recording.tx;
+        let mut changes = Vec::new();
+        for after in fetch_todo_rows(&mut tx, &recording.todo_ids, recording.last_todo_id).await? {
+            let before = recording.todos.remove(&after.todo_id);
+            if !same(&before, &Some(&after)) {
+                changes.push(Change::Todo { id: after.todo_id, before, after: Some(after) });
+            }
+        }
+        for (id, before) in recording.todos {
+            changes.push(Change::Todo { id, before: Some(before), after: None }); // deleted
+        }
+        for after in fetch_project_rows(&mut tx, &recording.project_ids, recording.last_project_id).await? {
+            let before = recording.projects.remove(&after.project_id);
+            if !same(&before, &Some(&after)) {
+                changes.push(Change::Project { id: after.project_id, before, after: Some(after) });
+            }
+        }
+        for (id, before) in recording.projects {
+            changes.push(Change::Project { id, before: Some(before), after: None });
+        }
+
+        if changes.is_empty() {
+            return Ok(tx.commit().await?);
+        }
+        let now = Local::now().naive_local();
+
+        for change in &changes {
+            if let Change::Todo { before, after, .. } = change {
+                record_history(&mut tx, self.source, now, before.as_ref(), after.as_ref()).await?;
+            }
+        }
+        let changes = serde_json::to_string(&changes)?;
+        sqlx::query!("DELETE FROM undo_log WHERE undone = TRUE")
+            .execute(&mut *tx)
+        .await?;
+        sqlx::query!("INSERT INTO undo_log (description, changes, date_created) VALUES (?, ?, ?)", description, changes, now)
+            .execute(&mut *tx)
+        .await?;
+        sqlx::query!("DELETE FROM undo_log WHERE entry_id <= (SELECT MAX(entry_id) FROM undo_log) - ?", UNDO_LIMIT)
+            .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Put back the rows of the last action. Returns its description, None when there is nothing to undo.
+    pub async fn undo(&self) -> Result<Option<String>, DbError> {
+        let entry = sqlx::query!("SELECT entry_id, description, changes FROM undo_log WHERE undone = FALSE ORDER BY entry_id DESC LIMIT 1")
+            .fetch_optional(&self.conn_pool)
+        .await?;
+
+        match entry {
+            Some(entry) => {
+                self.apply(&entry.changes, false, entry.entry_id, &entry.description).await?;
+                Ok(Some(entry.description))
+            }
+            None => Ok(None),
+        }
+    }
+
+    // Do the last undone action again
+    pub async fn redo(&self) -> Result<Option<String>, DbError> {
+        let entry = sqlx::query!("SELECT entry_id, description, changes FROM undo_log WHERE undone = TRUE ORDER BY entry_id LIMIT 1")
+            .fetch_optional(&self.conn_pool)
+        .await?;
+
+        match entry {
+            Some(entry) => {
+                self.apply(&entry.changes, true, entry.entry_id, &entry.description).await?;
+                Ok(Some(entry.description))
+            }
+            None => Ok(None),
+        }
+    }
+
+    // Write the after (redo) or before (undo) side of every change
+    async fn apply(&self, changes: &str, redo: bool, entry_id: i64, description: &str) -> Result<(), DbError> {
+        let changes: Vec<Change> = serde_json::from_str(changes)?;
+
+        let now = Local::now().naive_local();
+        let mut tx = self.conn_pool.begin().await?;
+        // Rows come back in any order, parents and projects may follow the rows pointing at them
+        sqlx::query!("PRAGMA defer_foreign_keys = ON")
+            .execute(&mut *tx)
+        .await?;
+
+        for change in changes {
+            match change {
+                Change::Todo { id, before, after } => {
+                    record_undo(&mut tx, self.source, now, id, redo, description).await?;
+                    match if redo { after } else { before } {
+                        Some(todo) => {
+                            let recurrence = todo.recurrence.as_ref().map(|rule| rule.to_string());
+                            sqlx::query!("INSERT INTO todo (todo_id, title, description, date_created, status, date_due, reminder_date,
+parent_todo, priority, project_id, recurrence, date_completed, archived, deleted_at)
+VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+ON CONFLICT(todo_id) DO UPDATE SET title = excluded.title, description = excluded.description,
+date_created = excluded.date_created, status = excluded.status, date_due = excluded.date_due,
+reminder_date = excluded.reminder_date, parent_todo = excluded.parent_todo, priority = excluded.priority,
+project_id = excluded.project_id, recurrence = excluded.recurrence, date_completed = excluded.date_completed,
+archived = excluded.archived, deleted_at = excluded.deleted_at",
+                                todo.todo_id, todo.title, todo.description, todo.date_created, todo.status, todo.date_due,
+                                todo.reminder_date, todo.parent_todo, todo.priority, todo.project_id, recurrence,
+                                todo.date_completed, todo.archived, todo.deleted_at)
+                                .execute(&mut *tx)
+                            .await?;
+                        }
+                        None => {
+                            sqlx::query!("DELETE FROM todo WHERE todo_id = ?", id)
+                                .execute(&mut *tx)
+                            .await?;
+                        }
+                    }
+                }
+                Change::Project { id, before, after } => {
+                    match if redo { after } else { before } {
+                        Some(project) => {
+                            sqlx::query!("INSERT INTO project (project_id, name, description, color, favorite_status) VALUES (?, ?, ?, ?, ?)
+ON CONFLICT(project_id) DO UPDATE SET name = excluded.name, description = excluded.description,
+color = excluded.color, favorite_status = excluded.favorite_status",
+                                project.project_id, project.name, project.description, project.color, project.favorite_status)
+                                .execute(&mut *tx)
+                            .await?;
+                        }
+                        None => {
+                            sqlx::query!("DELETE FROM project WHERE project_id = ?", id)
+                                .execute(&mut *tx)
+                            .await?;
+                        }
+                    }
+                }
+            }
+        }
+
+        let undone = !redo;
+        sqlx::query!("UPDATE undo_log SET undone = ? WHERE entry_id = ?", undone, entry_id)
+            .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Every todo below the given one, and the todo itself
+    pub async fn subtree_ids(&self, id: i64) -> Result<Vec<i64>, DbError> {
+        let rows = sqlx::query!(r#"WITH RECURSIVE subtree(todo_id) AS (
+    SELECT ?
+    UNION SELECT todo.todo_id FROM todo JOIN subtree ON todo.parent_todo = subtree.todo_id
+)
+SELECT todo_id AS "todo_id!: i64" FROM subtree"#, id)
+            .fetch_all(&self.conn_pool)
+        .await?;
+        Ok(rows.into_iter().map(|row| row.todo_id).collect())
+    }
+}
+
+// Rows are compared through their JSON, the structs don't implement PartialEq
+fn same<T: Serialize>(before: &Option<T>, after: &Option<&T>) -> bool {
+    serde_json::to_value(before).ok() == serde_json::to_value(after).ok()
+}
+
+// The given todos and any created after `last_id`
+async fn fetch_todo_rows(conn: &mut SqliteConnection, ids: &[i64], last_id: i64) -> Result<Vec<Todo>, DbError> {
+    let ids = serde_json::to_string(ids).unwrap_or_else(|_| String::from("[]"));
+    let rows = sqlx::query!("SELECT * FROM todo WHERE todo_id IN (SELECT value FROM json_each(?)) OR todo_id > ?", ids, last_id)
+        .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| todo_from_row!(row)).collect())
+}
+
+async fn fetch_project_rows(conn: &mut SqliteConnection, ids: &[i64], last_id: i64) -> Result<Vec<Project>, DbError> {
+    let ids = serde_json::to_string(ids).unwrap_or_else(|_| String::from("[]"));
+    let rows = sqlx::query!("SELECT * FROM project WHERE project_id IN (SELECT value FROM json_each(?)) OR project_id > ?", ids, last_id)
+        .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| Project {
+        project_id: row.project_id,
+        name: row.name,
+        description: row.description,
+        color: row.color,
+        favorite_status: row.favorite_status.unwrap_or(false),
+    }).collect())
+}
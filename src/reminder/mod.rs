@@ -0,0 +1,240 @@
+pub mod notify;
+
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+use chrono::{Local, NaiveDateTime};
+
+use crate::model::db::Db;
+use crate::model::error::DbError;
+use notify::{Notifier, NotifyError};
+
+// Longest sleep between checks, so reminders added or changed elsewhere are picked up
+const MAX_SLEEP: Duration = Duration::from_secs(30);
+
+// Where the engine gets the time from, swapped for a fake one in tests
+pub trait Clock {
+    fn now(&self) -> NaiveDateTime;
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send {
+        tokio::time::sleep(duration)
+    }
+}
+
+// Outcome of one pass over the reminders
+pub struct Check {
+    pub wait: Duration, // until the next reminder, at most MAX_SLEEP
+    pub failures: Vec<ReminderError>, // notifications that couldn't be sent
+}
+
+#[derive(Debug)]
+pub enum ReminderError {
+    Db(DbError), // checking for reminders failed, tried again on the next pass
+    Notify(String, NotifyError), // the title of the todo, and why its reminder wasn't sent
+}
+
+impl fmt::Display for ReminderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReminderError::Db(e) => write!(f, "could not check for reminders: {}", e),
+            ReminderError::Notify(title, e) => write!(f, "could not send the reminder for '{}': {}", title, e),
+        }
+    }
+}
+
+impl std::error::Error for ReminderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReminderError::Db(e) => Some(e),
+            ReminderError::Notify(_, e) => Some(e.as_ref()),
+        }
+    }
+}
+
+// Fires every open todo whose reminder_date passes while it runs, used by both the TUI and `day_list daemon`
+pub struct Reminders<C: Clock, N: Notifier> {
+    list_db: Db,
+    clock: C,
+    notifier: N,
+    since: NaiveDateTime, // reminders up to here have been sent
+}
+
+impl<C: Clock, N: Notifier> Reminders<C, N> {
+    // Only reminders from now on are sent, missed ones are not replayed
+    pub fn new(list_db: Db, clock: C, notifier: N) -> Reminders<C, N> {
+        let since = clock.now();
+        Reminders { list_db, clock, notifier, since }
+    }
+
+    // Failures are handed to `report`, the TUI can't have them printed over its screen
+    pub async fn run(mut self, mut report: impl FnMut(ReminderError) + Send) {
+        loop {
+            let sleep = match self.check().await {
+                Ok(check) => {
+                    for failure in check.failures {
+                        report(failure);
+                    }
+                    check.wait
+                }
+                Err(e) => {
+                    report(ReminderError::Db(e));
+                    MAX_SLEEP // database busy or gone, try again later
+                }
+            };
+            self.clock.sleep(sleep).await;
+        }
+    }
+
+    // Send the reminders in (since, now] and work out how long to wait for the next one
+    pub async fn check(&mut self) -> Result<Check, DbError> {
+        let now = self.clock.now();
+        let todos = self.list_db.fetch_reminders(self.since, now).await?;
+        let next = self.list_db.next_reminder(now).await?;
+
+        let mut failures = Vec::new();
+        for todo in &todos {
+            // One failed notification shouldn't hold back the rest
+            if let Err(e) = self.notifier.notify(todo).await {
+                failures.push(ReminderError::Notify(todo.title.clone(), e));
+            }
+        }
+        self.since = now;
+
+        let wait = next.and_then(|next| (next - now).to_std().ok())
+            .map(|wait| wait.min(MAX_SLEEP))
+            .unwrap_or(MAX_SLEEP);
+        Ok(Check { wait, failures })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::model::db::test_db;
+    use crate::model::schema::Todo;
+    use notify::NotifyError;
+
+    // Time only moves when a test, or a sleep, moves it
+    #[derive(Clone)]
+    struct FakeClock(Arc<Mutex<NaiveDateTime>>);
+
+    impl FakeClock {
+        fn advance(&self, seconds: i64) {
+            *self.0.lock().unwrap() += chrono::Duration::seconds(seconds);
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> NaiveDateTime {
+            *self.0.lock().unwrap()
+        }
+
+        fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send {
+            self.advance(duration.as_secs() as i64);
+            std::future::ready(())
+        }
+    }
+
+    // Keeps the ids of the todos it was asked to send
+    #[derive(Default)]
+    struct RecordingNotifier {
+        sent: Vec<i64>,
+    }
+
+    impl notify::Notifier for RecordingNotifier {
+        async fn notify(&mut self, todo: &Todo) -> Result<(), NotifyError> {
+            self.sent.push(todo.todo_id);
+            Ok(())
+        }
+    }
+
+    fn start() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 14).unwrap().and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    async fn add_reminder(list_db: &Db, title: &str, after_seconds: i64) -> i64 {
+        let todo = Todo { reminder_date: Some(start() + chrono::Duration::seconds(after_seconds)), ..Todo::titled(title) };
+        list_db.create_todo(&todo).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn sends_reminders_once_within_the_window() {
+        let list_db = test_db("reminder_window").await;
+        let missed = add_reminder(&list_db, "missed", -60).await;
+        let at_start = add_reminder(&list_db, "at start", 0).await;
+        let soon = add_reminder(&list_db, "soon", 10).await;
+        let later = add_reminder(&list_db, "later", 20).await;
+
+        let clock = FakeClock(Arc::new(Mutex::new(start())));
+        let mut reminders = Reminders::new(list_db, clock.clone(), RecordingNotifier::default());
+
+        // Nothing new yet, reminders before or at the start are not replayed
+        reminders.check().await.unwrap();
+        assert!(reminders.notifier.sent.is_empty());
+
+        // (since, now] includes the reminder due right now
+        clock.advance(10);
+        reminders.check().await.unwrap();
+        assert_eq!(reminders.notifier.sent, vec![soon]);
+
+        // and doesn't send it again
+        clock.advance(5);
+        reminders.check().await.unwrap();
+        assert_eq!(reminders.notifier.sent, vec![soon]);
+
+        clock.advance(100);
+        reminders.check().await.unwrap();
+        assert_eq!(reminders.notifier.sent, vec![soon, later]);
+        assert!(!reminders.notifier.sent.contains(&missed));
+        assert!(!reminders.notifier.sent.contains(&at_start));
+    }
+
+    #[tokio::test]
+    async fn skips_completed_todos() {
+        let list_db = test_db("reminder_completed").await;
+        let done = add_reminder(&list_db, "done", 5).await;
+        list_db.complete_todo(done).await.unwrap();
+
+        let clock = FakeClock(Arc::new(Mutex::new(start())));
+        let mut reminders = Reminders::new(list_db, clock.clone(), RecordingNotifier::default());
+        clock.advance(10);
+        reminders.check().await.unwrap();
+        assert!(reminders.notifier.sent.is_empty());
+    }
+
+    #[tokio::test]
+    async fn waits_until_the_next_reminder() {
+        let list_db = test_db("reminder_wait").await;
+        let clock = FakeClock(Arc::new(Mutex::new(start())));
+        let mut reminders = Reminders::new(list_db.clone(), clock.clone(), RecordingNotifier::default());
+
+        // Nothing pending, check back after the longest sleep
+        assert_eq!(reminders.check().await.unwrap().wait, MAX_SLEEP);
+
+        add_reminder(&list_db, "far", 3600).await;
+        assert_eq!(reminders.check().await.unwrap().wait, MAX_SLEEP);
+
+        add_reminder(&list_db, "near", 12).await;
+        assert_eq!(reminders.check().await.unwrap().wait, Duration::from_secs(12));
+
+        clock.advance(12);
+        let check = reminders.check().await.unwrap();
+        assert_eq!(check.wait, MAX_SLEEP);
+        assert!(check.failures.is_empty());
+        assert_eq!(reminders.notifier.sent.len(), 1);
+    }
+}
@@ -0,0 +1,95 @@
+use std::env;
+use std::error::Error;
+use std::future::Future;
+use std::process::Command;
+
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::model::schema::Todo;
+
+pub type NotifyError = Box<dyn Error + Send + Sync>;
+
+// How a reminder reaches the user
+pub trait Notifier {
+    fn notify(&mut self, todo: &Todo) -> impl Future<Output = Result<(), NotifyError>> + Send;
+}
+
+// Hands reminders to the TUI event loop
+pub struct ChannelNotifier(pub UnboundedSender<Todo>);
+
+impl Notifier for ChannelNotifier {
+    async fn notify(&mut self, todo: &Todo) -> Result<(), NotifyError> {
+        self.0.send(todo.clone())?;
+        Ok(())
+    }
+}
+
+pub struct StdoutNotifier;
+
+impl Notifier for StdoutNotifier {
+    async fn notify(&mut self, todo: &Todo) -> Result<(), NotifyError> {
+        println!("[{}] {}", todo.todo_id, todo.title);
+        Ok(())
+    }
+}
+
+// Runs the user's `notify_command` with the title as the last argument.
+// The todo is also described in DAY_LIST_ID, DAY_LIST_TITLE, DAY_LIST_DESCRIPTION and DAY_LIST_DUE.
+pub struct CommandNotifier {
+    pub command: Vec<String>,
+}
+
+impl Notifier for CommandNotifier {
+    async fn notify(&mut self, todo: &Todo) -> Result<(), NotifyError> {
+        let (program, args) = self.command.split_first().ok_or("notify_command is empty")?;
+        // Waited on without blocking the runtime, the TUI shares it
+        let status = tokio::process::Command::new(program)
+            .args(args)
+            .arg(&todo.title)
+            .env("DAY_LIST_ID", todo.todo_id.to_string())
+            .env("DAY_LIST_TITLE", &todo.title)
+            .env("DAY_LIST_DESCRIPTION", todo.description.as_deref().unwrap_or(""))
+            .env("DAY_LIST_DUE", todo.date_due.map(|due| due.to_string()).unwrap_or_default())
+            .status()
+            .await?;
+
+        if !status.success() {
+            return Err(format!("{} exited with {}", program, status).into());
+        }
+        Ok(())
+    }
+}
+
+// Desktop notification through the session D-Bus, sent with gdbus so no bus library is needed
+pub struct DesktopNotifier;
+
+impl DesktopNotifier {
+    // Only when there is a session bus to talk to
+    pub fn available() -> bool {
+        env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some()
+            && Command::new("gdbus").arg("help").output().is_ok()
+    }
+}
+
+impl Notifier for DesktopNotifier {
+    async fn notify(&mut self, todo: &Todo) -> Result<(), NotifyError> {
+        let output = tokio::process::Command::new("gdbus")
+            .args([
+                "call", "--session",
+                "--dest", "org.freedesktop.Notifications",
+                "--object-path", "/org/freedesktop/Notifications",
+                "--method", "org.freedesktop.Notifications.Notify",
+                "day_list", "0", "", // app name, replaces id, icon
+            ])
+            .arg(&todo.title)
+            .arg(todo.description.as_deref().unwrap_or(""))
+            .args(["[]", "{}", "-1"]) // actions, hints, default timeout
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string().into());
+        }
+        Ok(())
+    }
+}
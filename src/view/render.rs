@@ -1,60 +1,137 @@
-use std::error::Error;
+use std::io::Write;
+use std::time::Duration;
 
 use crossterm::event::{self, Event};
-use tui::layout::Constraint;
-use tui::style::{Color, Style};
-use tui::widgets::{Block, Borders, Paragraph, List, ListItem, Table, Row, Cell};
+use tokio::sync::mpsc;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Clear, Paragraph, List, ListItem, Table, Row, Cell, Wrap};
 use tui::Terminal;
-
+use chrono::{Datelike, Local, NaiveDateTime};
+use unicode_width::UnicodeWidthStr;
+
+use crate::FormatOptions;
+use crate::config::{Config, SavedState};
+use crate::error::AppError;
+use crate::model::recurrence::RECURRENCE_HELP;
+use crate::model::date_input::DATE_INPUT_HELP;
 use crate::model::db::Db;
 use crate::controller::nav::Widget;
 use crate::controller::nav::Content;
-use crate::controller::state::TodoList;
-use crate::controller::state::AppState;
+use crate::controller::state::{ListRow, TodoList, TreeRow};
+use crate::controller::state::{AlertLevel, AppState, PSEUDO_PROJECTS};
 use crate::controller::state::LayoutState;
 use crate::controller::handle;
+use crate::controller::edit_todo::{EditAction, EditSelection};
+use crate::controller::text_input::TextInput;
+use crate::reminder::{ReminderError, Reminders, SystemClock};
+use crate::reminder::notify::ChannelNotifier;
+
+// How long to wait for input before checking for reminders again
+const TICK: Duration = Duration::from_millis(250);
 
-pub async fn run<B>(terminal: &mut Terminal<B>) -> Result<(), Box<dyn Error>> 
+pub async fn run<B>(terminal: &mut Terminal<B>, list_db: Db, config: Config) -> Result<(), AppError> 
     where B: tui::backend::Backend
 {
 
-    // database conncection
-    let list_db = Db::new().await;
-
     // State
-    let mut app = AppState::init();
+    let mut app = AppState::init(config);
     let mut layout = LayoutState::init();
 
-    app.upcoming_list = list_db.fetch_upcoming_todos(app.todo_items_offset, app.todo_items_limit).await?;
-    app.todo_list = TodoList::new(list_db.fetch_todos(app.todo_items_offset, app.todo_items_limit).await?);
-    let mut todo_list = TodoList::new(list_db.fetch_todos(app.todo_items_offset, app.todo_items_limit).await?); // ERROR redundant
+    app.project_list.set_projects(list_db.fetch_projects().await?);
+    app.daylist = SavedState::load().daylist;
+    let mut todo_list = TodoList::new(Vec::new());
+    handle::reload_todos(&mut app, &mut todo_list, &list_db).await?;
+
+    let (reminder_tx, mut reminder_rx) = mpsc::unbounded_channel();
+    let (reminder_error_tx, mut reminder_error_rx) = mpsc::unbounded_channel();
+    let report = move |e: ReminderError| { let _ = reminder_error_tx.send(e); };
+    let reminder_task = tokio::spawn(Reminders::new(list_db.clone(), SystemClock, ChannelNotifier(reminder_tx)).run(report));
 
     loop {
         // my ghetto way to exit the program, forgot the right way
         if !app.is_running() { break; } 
 
+        while let Ok(todo) = reminder_rx.try_recv() {
+            if app.config.bell {
+                print!("\x07");
+                std::io::stdout().flush()?;
+            }
+            app.reminders.push_back(todo);
+        }
+
+        // Reported like a failed action. A failing check is retried every pass,
+        // so an error already waiting to be dismissed isn't queued again.
+        while let Ok(e) = reminder_error_rx.try_recv() {
+            let e = AppError::from(e);
+            if !e.is_recoverable() {
+                reminder_task.abort();
+                return Err(e);
+            }
+            let message = e.to_string();
+            if !app.alerts.iter().any(|alert| alert.message == message) {
+                app.alert(AlertLevel::Error, message);
+            }
+        }
+
         terminal.draw(|frame| {
-            layout.structure(frame.size());
+            let format_options = FormatOptions { date_format: &app.config.date_format, ..FormatOptions::default() };
+
+            // The bottom row grows to fit every shortcut
+            let keybind_rows = keybind_rows(&keybinds(&app), frame.size().width.saturating_sub(2));
+            layout.structure(frame.size(), keybind_rows.len() as u16);
             layout.update_bounds();
 
             // TODO move to edit_todo
             // Edit todo... {
+            let parent_line = app.edit.parent_todo
+                .and_then(|id| todo_list.todos.iter().find(|todo| todo.todo_id == id))
+                .map(|parent| format!("Subtask of: {}", parent.title))
+                .unwrap_or_default();
+            let recurrence_hint = match app.edit.parse_recurrence() {
+                Err(_) => format!("(not understood: {})", RECURRENCE_HELP),
+                Ok(None) if matches!(app.edit.selection, EditSelection::Recurrence) => format!("({})", RECURRENCE_HELP),
+                _ => String::new(),
+            };
+            let due_hint = date_hint(app.edit.parse_due(), matches!(app.edit.selection, EditSelection::DateDue), &app.config.date_format);
+            let reminder_hint = date_hint(app.edit.parse_reminder(), matches!(app.edit.selection, EditSelection::ReminderDate), &app.config.date_format);
+            let project_hint = match app.edit.selection {
+                EditSelection::Project if !app.edit.project.is_empty() => {
+                    let completions = app.edit.project_completions(&app.project_list.projects);
+                    if completions.is_empty() {
+                        String::from("(new project)")
+                    } else {
+                        format!("(Tab: {})", completions.join(", "))
+                    }
+                }
+                _ => String::new(),
+            };
+            // The field being typed in scrolls to keep the cursor in the form
+            let edit_column = edit_field_position(&app.edit.selection).map_or(0, |(_, column)| column);
+            let field_width = layout.center_column[1].width.saturating_sub(2 + edit_column);
             let edit_string = format!("
                 Title: {}
                
                 Description: {}
-              
 
-                DD/MM/YY HH:MM
+                Project: {} {}
+                {}
 
-                Date Due: {}
+                Date Due: {} {}
              
-                Reminder Date: {}
+                Reminder Date: {} {}
+
+                Repeat: {} {}
             
                 Priority 1-9:  {}
                 ",
-                app.edit.name, app.edit.description,
-                app.edit.date_due, app.edit.reminder_date,
+                field_text(&app.edit.name, app.edit.selection == EditSelection::Name, field_width),
+                field_text(&app.edit.description, app.edit.selection == EditSelection::Description, field_width),
+                field_text(&app.edit.project, app.edit.selection == EditSelection::Project, field_width), project_hint, parent_line,
+                field_text(&app.edit.date_due, app.edit.selection == EditSelection::DateDue, field_width), due_hint,
+                field_text(&app.edit.reminder_date, app.edit.selection == EditSelection::ReminderDate, field_width), reminder_hint,
+                field_text(&app.edit.recurrence, app.edit.selection == EditSelection::Recurrence, field_width), recurrence_hint,
                 app.edit.priority
             );
             let edit_item = vec![ListItem::new(edit_string)];
@@ -64,11 +141,24 @@ pub async fn run<B>(terminal: &mut Terminal<B>) -> Result<(), Box<dyn Error>>
                 .highlight_style(Style::default());
             // Edit todo... }
 
+            let list_title = match app.main_content_shown {
+                Content::SearchResults => format!("Search Result - {}", app.search_query),
+                Content::Day(day) => format!("Agenda - {}", day.format("%a %d %b %Y")),
+                Content::Smart(view) => String::from(view.name()),
+                Content::Completed => String::from("Completed"),
+                Content::Trash => format!("Trash - purged after {} days", app.config.trash_retention_days),
+                _ => format!("List - {} - sort: {} - group: {}{}", app.project_list.filter_name(),
+                    app.daylist.sort.name(), app.daylist.group.name(),
+                    if app.daylist.hide_completed { " - completed hidden" } else { "" }),
+            };
             let daylist_todos = List::new(
-                todo_list.todos.iter()
-                    .map(|todo| ListItem::new(todo.format()).style(Style::default().fg(Color::White)))
+                todo_list.rows.iter()
+                    .map(|row| match row {
+                        ListRow::Group(label) => ListItem::new(label.clone()).style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                        ListRow::Todo(row) => ListItem::new(tree_item(&todo_list, row, &app.config.date_format)).style(Style::default().fg(Color::White)),
+                    })
                     .collect::<Vec<ListItem<'_>>>()) // probably suboptimal
-                .block(Block::default().borders(Borders::ALL).title("List"))
+                .block(Block::default().borders(Borders::ALL).title(list_title))
                 .highlight_style(Style::default().fg(Color::Yellow).bg(Color::Black)); // Highlight the selected item
 
             // Initialize calendar content here
@@ -76,41 +166,29 @@ pub async fn run<B>(terminal: &mut Terminal<B>) -> Result<(), Box<dyn Error>>
 
             layout.upcoming_content = List::new(
                 app.upcoming_list.iter()
-                    .map(|todo| ListItem::new(todo.format()).style(Style::default().fg(Color::White)))
+                    .map(|todo| ListItem::new(todo.format(&format_options)).style(Style::default().fg(Color::White)))
                     .collect::<Vec<ListItem<'_>>>()) // probably suboptimal
-                .block(Block::default().borders(Borders::ALL).title("Upcoming"))
+                .block(Block::default().borders(Borders::ALL).title(format!("Upcoming - {}", app.upcoming_view.name())))
                 .highlight_style(Style::default().fg(Color::Yellow).bg(Color::Black)); // Highlight the selected item
 
-            //let search_content = generate_search_results(&app, &mut layout); // ERROR
-            layout.search_box = Paragraph::new(app.search_string.clone()).block(Block::default().title("Search")
+            let search_width = layout.center_column[0].width.saturating_sub(2);
+            layout.search_box = Paragraph::new(field_text(&app.search_string, true, search_width)).block(Block::default().title("Search")
                 .borders(Borders::ALL));
-            let search_results = app.search_results.iter()
-                .map(|todo| ListItem::new(todo.format()).style(Style::default().fg(Color::White)))
-                .collect::<Vec<ListItem<'_>>>(); // probably suboptimal
-            let search_content = List::new(search_results)
-                .block(Block::default().borders(Borders::ALL).title("Search Result"))
-                .highlight_style(Style::default().fg(Color::Yellow).bg(Color::Black)); // Highlight the selected item);
 
             match app.main_content_shown {
-                Content::Daylist => layout.main_content = daylist_todos,
+                Content::Daylist | Content::SearchResults | Content::Day(_) | Content::Smart(_) | Content::Completed | Content::Trash => layout.main_content = daylist_todos,
                 Content::EditTodo => layout.main_content = edit_todo,
-                Content::SearchResults => layout.main_content = search_content,
-                _ => {},
             }
 
-            // actually put content in that mf. 
-            // Later extract to its own generate function
-            layout.projects_content = List::new(vec![])
-                .block(Block::default().title("Projects").borders(Borders::ALL))
-                .highlight_style(Style::default());
+            generate_projects(&app, &mut layout);
 
 
-            generate_calendar(&mut layout);
+            generate_calendar(&app, &mut layout);
 
-            show_focused_widget(&app, &mut layout);
+            show_focused_widget(&app, &mut layout, keybind_rows);
 
             frame.render_widget(layout.logo_block.clone(), layout.left_column[0]);
-            frame.render_widget(layout.projects_content.clone(), layout.left_column[1]);
+            frame.render_stateful_widget(layout.projects_content.clone(), layout.left_column[1], &mut app.project_list.state);
 
             frame.render_widget(layout.bottom_row_content.clone(), layout.chunks[1]);
 
@@ -119,51 +197,187 @@ pub async fn run<B>(terminal: &mut Terminal<B>) -> Result<(), Box<dyn Error>>
             frame.render_stateful_widget(layout.main_content.clone(), layout.center_column[1], &mut todo_list.state);
 
             // TODO - Sacrifice rendering these if the terminal size becomes too small
-            frame.render_stateful_widget(layout.upcoming_content.clone(), layout.right_column[0], &mut todo_list.state);
+            frame.render_widget(layout.upcoming_content.clone(), layout.right_column[0]);
             // Supposed to be stateful but I need to create a struct for tables
             frame.render_widget(layout.calendar_content.clone(), layout.right_column[1]);
 
+            if let Some(panel) = &app.history {
+                let area = centered_rect(70, 60, frame.size());
+                let lines = match panel.entries.is_empty() {
+                    true => String::from("No changes recorded yet"),
+                    false => panel.entries.iter()
+                        .map(|entry| entry.format(&app.config.date_format))
+                        .collect::<Vec<String>>()
+                        .join("\n"),
+                };
+                let popup = Paragraph::new(lines)
+                    .block(Block::default().borders(Borders::ALL).title(format!("History of \"{}\"", panel.title)))
+                    .style(Style::default().fg(Color::Yellow))
+                    .scroll((panel.scroll, 0));
+                frame.render_widget(Clear, area);
+                frame.render_widget(popup, area);
+            }
+
+            if let Some(todo) = app.reminders.front() {
+                let area = centered_rect(50, 30, frame.size());
+                let title = match app.reminders.len() {
+                    1 => String::from("Reminder"),
+                    n => format!("Reminder (1 of {})", n),
+                };
+                let popup = Paragraph::new(todo.format(&format_options))
+                    .block(Block::default().borders(Borders::ALL).title(title))
+                    .style(Style::default().fg(Color::Yellow))
+                    .wrap(Wrap { trim: false });
+                frame.render_widget(Clear, area);
+                frame.render_widget(popup, area);
+            }
+
+            if let Some(confirm) = &app.confirm {
+                let area = centered_rect(40, 20, frame.size());
+                let popup = Paragraph::new(format!("{}\n\ny|Yes  n|No", confirm.question()))
+                    .block(Block::default().borders(Borders::ALL).title("Confirm"))
+                    .style(Style::default().fg(Color::Yellow))
+                    .wrap(Wrap { trim: false });
+                frame.render_widget(Clear, area);
+                frame.render_widget(popup, area);
+            }
+
+            if let Some(alert) = app.alerts.front() {
+                let area = centered_rect(40, 20, frame.size());
+                let title = match app.alerts.len() {
+                    1 => alert.level.name().to_string(),
+                    n => format!("{} (1 of {})", alert.level.name(), n),
+                };
+                let color = match alert.level {
+                    AlertLevel::Info => Color::Cyan,
+                    AlertLevel::Warning => Color::Yellow,
+                    AlertLevel::Error => Color::Red,
+                };
+                let popup = Paragraph::new(format!("{}\n\nEnter|OK", alert.message))
+                    .block(Block::default().borders(Borders::ALL).title(title))
+                    .style(Style::default().fg(color))
+                    .wrap(Wrap { trim: false });
+                frame.render_widget(Clear, area);
+                frame.render_widget(popup, area);
+            }
+
+            // Show the terminal cursor in the field being typed in, unless a popup is over it
+            let cursor = match app.focused_widget {
+                _ if app.popup_shown() => None,
+                Widget::Search => Some((layout.center_column[0], 0, 0, app.search_string.view(search_width).0)),
+                Widget::EditTodo => edit_field_position(&app.edit.selection)
+                    .zip(app.edit.selected_field())
+                    .map(|((row, column), input)| (layout.center_column[1], row, column, input.view(field_width).0)),
+                _ => None,
+            };
+            if let Some((area, row, column, before)) = cursor {
+                // Inside the border, right after the text before it
+                let width = u16::try_from(before.width()).unwrap_or(u16::MAX);
+                let x = (area.x + 1).saturating_add(column).saturating_add(width);
+                frame.set_cursor(x, area.y + 1 + row);
+            }
+
             //render_layout(layout, &mut f);
         })?;
 
 
+        // Wake up regularly so reminders show without a keypress
+        if !event::poll(TICK)? {
+            continue;
+        }
+
         // Match on different types of events
         let user_event = event::read()?;
+        if let Event::Key(_) = user_event {
+            app.status = None;
+        }
         match user_event {
             // Handle keyboard events
-            Event::Key(key) => match app.focused_widget {
-                Widget::Search => handle::search_events(key, &mut app, &list_db).await?,
-                Widget::Main => handle::list_events(key, &mut app, &mut todo_list, &list_db).await?,
-                Widget::EditTodo => handle::edit_events(key, &mut app, &mut todo_list, &list_db).await?,
-                _ => handle::default_events(key, &mut app),
-            },
+            Event::Key(key) => {
+                // Popups on top take the key first: alerts, then reminders, then the history panel,
+                // and a pending question takes every key until it is answered
+                let handled = if !app.alerts.is_empty() {
+                    handle::alert_events(key, &mut app);
+                    Ok(())
+                } else if !app.reminders.is_empty() {
+                    handle::reminder_events(key, &mut app, &mut todo_list, &list_db).await
+                } else if app.history.is_some() {
+                    handle::history_events(key, &mut app);
+                    Ok(())
+                } else if app.confirm.is_some() {
+                    handle::confirm_events(key, &mut app, &mut todo_list, &list_db).await
+                } else {
+                    match app.focused_widget {
+                        Widget::Search => handle::search_events(key, &mut app, &mut todo_list, &list_db).await,
+                        Widget::Main => handle::list_events(key, &mut app, &mut todo_list, &list_db).await,
+                        Widget::EditTodo => handle::edit_events(key, &mut app, &mut todo_list, &list_db).await,
+                        Widget::Projects => handle::project_events(key, &mut app, &mut todo_list, &list_db).await,
+                        Widget::EditProject => handle::edit_project_events(key, &mut app, &list_db).await,
+                        Widget::Calendar => handle::calendar_events(key, &mut app, &mut todo_list, &list_db).await,
+                        Widget::Upcoming => handle::upcoming_events(key, &mut app, &mut todo_list, &list_db).await,
+                    }
+                };
+
+                // A failed action is reported and the app carries on, the user can try again
+                match handled {
+                    Err(e) if e.is_recoverable() => app.alert(AlertLevel::Error, e.to_string()),
+                    Err(e) => {
+                        reminder_task.abort();
+                        return Err(e);
+                    }
+                    Ok(()) => {}
+                }
+            }
+
+
+            // Pasted text arrives in one piece instead of as key presses
+            Event::Paste(text) => handle::paste_events(&text, &mut app),
 
+            // Default Mouse handling
+            Event::Mouse(mouse_event) => {
+                if let crossterm::event::MouseEventKind::Down(_) = mouse_event.kind {
+                    //button, mouse_event.column, mouse_event.row
 
-            Event::Mouse(mouse_event) => match app.focused_widget {
-                // Default Mouse handling
-                _ => match mouse_event.kind {
-                    crossterm::event::MouseEventKind::Down(button) => {
-                        //button, mouse_event.column, mouse_event.row
-
-                        // Check if the mouse click is within the bounds of the search bar
-                        if mouse_event.column >= layout.search_bounds.x
-                        && mouse_event.column < layout.search_bounds.x + layout.search_bounds.width
-                        && mouse_event.row >= layout.search_bounds.y
-                        && mouse_event.row < layout.search_bounds.y + layout.search_bounds.height
-                        {
-                            app.focused_widget = Widget::Search;
-                        }
-
-                        if mouse_event.column >= layout.main_bounds.x
-                        && mouse_event.column < layout.main_bounds.x + layout.main_bounds.width
-                        && mouse_event.row >= layout.main_bounds.y
-                        && mouse_event.row < layout.main_bounds.y + layout.main_bounds.height
-                        {
-                            app.focused_widget = Widget::Main;
-                        }
+                    // Check if the mouse click is within the bounds of the search bar
+                    if mouse_event.column >= layout.search_bounds.x
+                    && mouse_event.column < layout.search_bounds.x + layout.search_bounds.width
+                    && mouse_event.row >= layout.search_bounds.y
+                    && mouse_event.row < layout.search_bounds.y + layout.search_bounds.height
+                    {
+                        app.focused_widget = Widget::Search;
                     }
-                    _ => {}
 
+                    if mouse_event.column >= layout.main_bounds.x
+                    && mouse_event.column < layout.main_bounds.x + layout.main_bounds.width
+                    && mouse_event.row >= layout.main_bounds.y
+                    && mouse_event.row < layout.main_bounds.y + layout.main_bounds.height
+                    {
+                        app.focused_widget = Widget::Main;
+                    }
+
+                    if mouse_event.column >= layout.projects_bounds.x
+                    && mouse_event.column < layout.projects_bounds.x + layout.projects_bounds.width
+                    && mouse_event.row >= layout.projects_bounds.y
+                    && mouse_event.row < layout.projects_bounds.y + layout.projects_bounds.height
+                    {
+                        app.focused_widget = Widget::Projects;
+                    }
+
+                    if mouse_event.column >= layout.upcoming_bounds.x
+                    && mouse_event.column < layout.upcoming_bounds.x + layout.upcoming_bounds.width
+                    && mouse_event.row >= layout.upcoming_bounds.y
+                    && mouse_event.row < layout.upcoming_bounds.y + layout.upcoming_bounds.height
+                    {
+                        app.focused_widget = Widget::Upcoming;
+                    }
+
+                    if mouse_event.column >= layout.calendar_bounds.x
+                    && mouse_event.column < layout.calendar_bounds.x + layout.calendar_bounds.width
+                    && mouse_event.row >= layout.calendar_bounds.y
+                    && mouse_event.row < layout.calendar_bounds.y + layout.calendar_bounds.height
+                    {
+                        app.focused_widget = Widget::Calendar;
+                    }
                 }
             },
 
@@ -177,136 +391,380 @@ pub async fn run<B>(terminal: &mut Terminal<B>) -> Result<(), Box<dyn Error>>
 
     } //running loop
 
+    reminder_task.abort();
 
     Ok(())
 } //run
 
-fn generate_search_results<'a>(app: &'a AppState, layout: &'a mut LayoutState<'a>) -> List<'a> {
-            layout.search_box = Paragraph::new(app.search_string.clone()).block(Block::default().title("Search")
-                .borders(Borders::ALL));
-            let search_results = app.search_results.iter()
-                .map(|todo| ListItem::new(todo.format()).style(Style::default().fg(Color::White)))
-                .collect::<Vec<ListItem<'_>>>(); // probably suboptimal
-            let search_content = List::new(search_results)
-                .block(Block::default().borders(Borders::ALL).title("Search Result"))
-                .highlight_style(Style::default().fg(Color::Yellow).bg(Color::Black)); // Highlight the selected item);
-
-            search_content
+// What a text field shows: the part around the cursor when it is being typed in, all of it otherwise
+fn field_text(input: &TextInput, selected: bool, width: u16) -> String {
+    match selected {
+        true => {
+            let (before, after) = input.view(width);
+            format!("{}{}", before, after)
+        }
+        false => input.to_string(),
+    }
+}
+
+// A text field in a list row, where the terminal cursor can't be placed as the list may have
+// scrolled: the cell under the cursor is drawn reversed instead
+fn input_spans(prefix: String, input: &TextInput, width: u16) -> Spans<'static> {
+    let (before, after) = input.view(width);
+    let mut rest = after.chars();
+    let under = rest.next().map_or(String::from(" "), String::from);
+    Spans::from(vec![
+        Span::raw(prefix),
+        Span::raw(before.to_string()),
+        Span::styled(under, Style::default().add_modifier(Modifier::REVERSED)),
+        Span::raw(rest.as_str().to_string()),
+    ])
+}
+
+// Row and column where the text of a field starts in the edit form below
+fn edit_field_position(selection: &EditSelection) -> Option<(u16, u16)> {
+    const INDENT: u16 = 16;
+    let (row, label) = match selection {
+        EditSelection::Name => (1, "Title: "),
+        EditSelection::Description => (3, "Description: "),
+        EditSelection::Project => (5, "Project: "),
+        EditSelection::DateDue => (8, "Date Due: "),
+        EditSelection::ReminderDate => (10, "Reminder Date: "),
+        EditSelection::Recurrence => (12, "Repeat: "),
+        EditSelection::Priority => return None,
+    };
+    Some((row, INDENT + label.len() as u16))
+}
+
+// What a date field was read as, or why it couldn't be
+fn date_hint(parsed: Result<Option<NaiveDateTime>, String>, selected: bool, date_format: &str) -> String {
+    match parsed {
+        Ok(Some(date)) => format!("→ {}", date.format(date_format)),
+        Err(e) => format!("(not understood: {}; {})", e, DATE_INPUT_HELP),
+        Ok(None) if selected => format!("({})", DATE_INPUT_HELP),
+        Ok(None) => String::new(),
+    }
+}
+
+// Indent a todo under its parent and mark whether its subtasks are folded
+fn tree_item(todo_list: &TodoList, row: &TreeRow, date_format: &str) -> String {
+    let todo = &todo_list.todos[row.index];
+    let indent = "    ".repeat(row.depth);
+    let marker = match (row.has_children, todo_list.collapsed.contains(&todo.todo_id)) {
+        (false, _) => "  ",
+        (true, false) => "▾ ",
+        (true, true) => "▸ ",
+    };
+
+    let options = FormatOptions {
+        progress: todo_list.progress.get(&todo.todo_id).copied(),
+        date_format,
+    };
+
+    todo.format(&options).lines()
+        .enumerate()
+        .map(|(i, line)| match i {
+            1 => format!("{}{}{}", indent, marker, line), // title line
+            _ => format!("{}  {}", indent, line),
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn generate_projects(app: &AppState, layout: &mut LayoutState) {
+    let name_width = layout.left_column[1].width.saturating_sub(4); // borders, marker and space
+    let mut items = vec![
+        ListItem::new("  All").style(Style::default().fg(Color::White)),
+        ListItem::new("  Inbox").style(Style::default().fg(Color::White)),
+    ];
+    items.extend(app.project_list.projects.iter()
+        .enumerate()
+        .map(|(i, project)| {
+            let marker = if project.favorite_status { "★" } else { " " };
+            let line = match (&app.focused_widget, &app.project_action) {
+                // Rename happens in place
+                (Widget::EditProject, EditAction::Update) if app.project_list.state.selected() == Some(i + PSEUDO_PROJECTS) =>
+                    input_spans(format!("{} ", marker), &app.project_name, name_width),
+                _ => Spans::from(format!("{} {}", marker, project.name)),
+            };
+            ListItem::new(line).style(Style::default().fg(Color::White))
+        }));
+
+    if let (Widget::EditProject, EditAction::Create) = (&app.focused_widget, &app.project_action) {
+        items.push(ListItem::new(input_spans(String::from("+ "), &app.project_name, name_width)).style(Style::default().fg(Color::Yellow)));
+    }
+
+    layout.projects_content = List::new(items)
+        .block(Block::default().title("Projects").borders(Borders::ALL))
+        .highlight_style(Style::default().fg(Color::Yellow).bg(Color::Black));
 }
 
-fn generate_calendar(layout: &mut LayoutState) {
-    // ERROR calendar proof of concept
-    let days = ["sun", "mon", "tue", "wed", "thur", "fri", "sat"];
-    let week = ["1","1","1","1","1","1","1"];
+const CALENDAR_WIDTHS: [Constraint; 7] = [Constraint::Ratio(1, 7); 7];
+
+fn generate_calendar(app: &AppState, layout: &mut LayoutState) {
+    let days = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
     let day_row = Row::new(days).style(Style::default().fg(Color::Yellow));
-    let week_row = Row::new(week).style(Style::default().fg(Color::Yellow));
-
-    layout.calendar_content = Table::new(vec![day_row, week_row.clone(), week_row.clone(), week_row.clone(), week_row.clone(), week_row])
-        .block(Block::default().borders(Borders::ALL))
-        .widths(&[
-            Constraint::Percentage(10),
-            Constraint::Percentage(10),
-            Constraint::Percentage(10),
-            Constraint::Percentage(15),
-            Constraint::Percentage(15),
-            Constraint::Percentage(15),
-        ]);
+    let today = Local::now().date_naive();
+
+    let mut rows = vec![day_row];
+    for week in app.calendar.weeks() {
+        let cells = week.iter().map(|day| match day {
+            Some(day) => {
+                // Days with something due get a dot
+                let text = match app.calendar.due_days.contains(day) {
+                    true => format!("{}•", day.day()),
+                    false => day.day().to_string(),
+                };
+                let mut style = Style::default().fg(Color::White);
+                if app.calendar.due_days.contains(day) {
+                    style = style.fg(Color::Cyan);
+                }
+                if *day == today {
+                    style = style.fg(Color::Black).bg(Color::White);
+                }
+                if *day == app.calendar.selected {
+                    if let Widget::Calendar = app.focused_widget {
+                        style = style.fg(Color::Black).bg(Color::Yellow);
+                    }
+                }
+                Cell::from(text).style(style)
+            }
+            None => Cell::from(""),
+        }).collect::<Vec<Cell<'_>>>();
+        rows.push(Row::new(cells));
+    }
+
+    layout.calendar_content = Table::new(rows)
+        .block(Block::default().borders(Borders::ALL).title(app.calendar.selected.format("%B %Y").to_string()))
+        .widths(&CALENDAR_WIDTHS);
 }
 
-fn show_focused_widget(app: &AppState, layout: &mut LayoutState) {
-    // A list for the bottom row showing keyboard shortcuts
+// The keyboard shortcuts for whatever has the keys
+fn keybinds(app: &AppState) -> Vec<String> {
     let default_keybinds = vec![
-        Cell::from("q|Quit"),
-        Cell::from("Esc|Home"),
-        Cell::from("n|New"),
-        Cell::from("e|Edit"),
-        Cell::from("d|Complete todo"),
-        Cell::from("X|Delete todo"),
-        Cell::from("L|List todos"),
-        Cell::from("Tab|Navigate Todos"),
+        "q|Quit",
+        "Esc|Home",
+        "n|New",
+        "e|Edit",
+        "d|Complete todo",
+        "X|Delete todo",
+        "s|Subtask",
+        "Space|Fold",
+        "m|Move todo",
+        "L|List todos",
+        "v|Views",
+        "o|Sort",
+        "g|Group",
+        "c|Hide done",
+        "C|Completed",
+        "A|Archive old",
+        "T|Trash",
+        "u|Undo",
+        "Ctrl-r|Redo",
+        "H|History",
+        "Tab|Navigate Todos",
     ];
 
     let search_keybinds = vec![
-        Cell::from("Esc|Home"),
-        Cell::from("d|Complete todo"),
-        Cell::from("e|Edit"),
-        Cell::from("X|Delete todo"),
-        Cell::from("Tab|Navigate Todos"),
-        Cell::from("Enter|Search!"),
+        "Esc|Home",
+        "d|Complete todo",
+        "e|Edit",
+        "X|Delete todo",
+        "Tab|Navigate Todos",
+        "Enter|Search!",
+    ];
+
+    let trash_keybinds = vec![
+        "q|Quit",
+        "Esc|Home",
+        "r|Restore",
+        "X|Delete for good",
+        "u|Undo",
+        "Tab|Navigate Todos",
     ];
 
     let calendar_keybinds = vec![
-        Cell::from("q|Quit"),
-        Cell::from("Esc|Home"),
-        Cell::from("n|New"),
-        Cell::from("Tab|Navigate Todos"),
-        Cell::from("Arrows|Navigate Calendar"),
+        "q|Quit",
+        "Esc|Home",
+        "n|New",
+        "Tab|Navigate Todos",
+        "Arrows|Navigate Calendar",
+        "PgUp/PgDn|Change month",
+        "Enter|Show day",
     ];
 
     let upcoming_keybinds = vec![
-        Cell::from("q|Quit"),
-        Cell::from("Esc|Home"),
-        Cell::from("n|New"),
-        Cell::from("e|Edit"),
-        Cell::from("d|Complete todo"),
-        Cell::from("X|Delete todo"),
-        Cell::from("L|List todos"),
-        Cell::from("Tab|Navigate Todos"),
+        "q|Quit",
+        "Esc|Home",
+        "Tab|Switch view",
+        "Enter|Open view",
     ];
 
     let projects_keybinds = vec![
-        Cell::from("q|Quit"),
-        Cell::from("Esc|Home"),
-        Cell::from("n|New"),
-        Cell::from("X|Delete"),
-        Cell::from("e|Edit"),
-        Cell::from("Tab|Navigate Projects"),
+        "q|Quit",
+        "Esc|Home",
+        "n|New",
+        "X|Delete",
+        "e|Edit",
+        "f|Favorite",
+        "Tab|Navigate Projects",
     ];
 
-    let mut current_keybinds = default_keybinds;
+    let move_todo_keybinds = vec![
+        "Esc|Cancel",
+        "Tab|Navigate Projects",
+        "Enter|Move todo here",
+    ];
+
+    let edit_project_keybinds = vec![
+        "Esc|Cancel",
+        "Enter|Save project",
+    ];
+
+    if !app.alerts.is_empty() {
+        return vec![String::from("Enter|OK")];
+    } else if !app.reminders.is_empty() {
+        return ["1|Snooze 10m", "2|Snooze 1h", "3|Tomorrow 9:00", "Enter|Dismiss"].map(String::from).to_vec();
+    } else if app.history.is_some() {
+        return ["Esc|Close", "Up/Down|Scroll"].map(String::from).to_vec();
+    } else if let Some(confirm) = &app.confirm {
+        return vec![confirm.question(), String::from("y|Yes"), String::from("n|No")];
+    }
+
+    let current_keybinds = match app.focused_widget {
+        Widget::Main if app.main_content_shown == Content::Trash => trash_keybinds,
+        Widget::Search => search_keybinds,
+        Widget::Calendar => calendar_keybinds,
+        Widget::Upcoming => upcoming_keybinds,
+        Widget::Projects => match app.moving_todo {
+            Some(_) => move_todo_keybinds,
+            None => projects_keybinds,
+        },
+        Widget::EditProject => edit_project_keybinds,
+        _ => default_keybinds,
+    };
+    current_keybinds.into_iter().map(String::from).collect()
+}
+
+// Lay the shortcuts out in equal columns, as many rows as it takes to fit them all in `width`
+fn keybind_rows(keybinds: &[String], width: u16) -> Vec<String> {
+    let column = keybinds.iter().map(|keybind| keybind.width()).max().unwrap_or(0) + 2;
+    let per_row = (usize::from(width) / column).max(1);
+
+    keybinds.chunks(per_row)
+        .map(|row| row.iter()
+            .map(|keybind| format!("{}{}", keybind, " ".repeat(column - keybind.width())))
+            .collect::<String>()
+            .trim_end()
+            .to_string())
+        .collect()
+}
 
+fn show_focused_widget(app: &AppState, layout: &mut LayoutState, keybind_rows: Vec<String>) {
     // Display the focused widget in the main content area
     match app.focused_widget {
-        Widget::Main => {
-            layout.main_content = layout.main_content.clone().style(Style::default().fg(Color::Yellow));
-            // default keybinds
-        }
         Widget::Search => {
             layout.search_box = layout.search_box.clone().style(Style::default().fg(Color::Yellow));
-            current_keybinds = search_keybinds;
         }
         Widget::Calendar => {
             layout.calendar_content = layout.calendar_content.clone().style(Style::default().fg(Color::Yellow));
-            current_keybinds = calendar_keybinds;
         }
         Widget::Upcoming => {
             layout.upcoming_content = layout.upcoming_content.clone().style(Style::default().fg(Color::Yellow));
-            current_keybinds = upcoming_keybinds;
         }
-        Widget::Projects => {
+        Widget::Projects | Widget::EditProject => {
             layout.projects_content = layout.projects_content.clone().style(Style::default().fg(Color::Yellow));
-            current_keybinds = projects_keybinds;
         }
         _ => {
             layout.main_content = layout.main_content.clone().style(Style::default().fg(Color::Yellow));
-            // default keybinds
         }
     };
 
-    let bottom_row_list = Row::new(current_keybinds).style(Style::default().fg(Color::Yellow));
-
-    layout.bottom_row_content = Table::new(vec![bottom_row_list])
-        .block(Block::default().borders(Borders::ALL))
-        .widths(&[
-            Constraint::Percentage(10),
-            Constraint::Percentage(10),
-            Constraint::Percentage(10),
-            Constraint::Percentage(15),
-            Constraint::Percentage(15),
-            Constraint::Percentage(15),
-        ]);
+    // The outcome of an undo or redo goes on the border, the rows are too narrow for it
+    let status = app.status.clone().unwrap_or_default();
+    layout.bottom_row_content = Paragraph::new(keybind_rows.join("\n"))
+        .block(Block::default().borders(Borders::ALL).title(status))
+        .style(Style::default().fg(Color::Yellow))
+        .wrap(Wrap { trim: false });
+}
 
+// A rectangle in the middle of `area`, sized in percent of it
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(area);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(rows[1])[1]
 }
 
 //fn render_layout<'a, B>(layout: LayoutState<'a>, f: tui::Frame<'a, B>) -> Layout_State<'a> where B: Backend {layout}
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui::backend::TestBackend;
+
+    // The screen's bottom row as drawn in a terminal of the given size, one string per line
+    fn bottom_row(app: &AppState, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        let mut layout = LayoutState::init();
+        terminal.draw(|frame| {
+            let keybind_rows = keybind_rows(&keybinds(app), frame.size().width.saturating_sub(2));
+            layout.structure(frame.size(), keybind_rows.len() as u16);
+            show_focused_widget(app, &mut layout, keybind_rows);
+            frame.render_widget(layout.bottom_row_content.clone(), layout.chunks[1]);
+        }).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let area = layout.chunks[1];
+        (area.top()..area.bottom())
+            .map(|y| (area.left()..area.right()).map(|x| buffer.get(x, y).symbol.as_str()).collect())
+            .collect()
+    }
+
+    fn shows(lines: &[String], keybind: &str) -> bool {
+        lines.iter().any(|line| line.contains(keybind))
+    }
+
+    #[test]
+    fn every_default_keybind_is_shown() {
+        let app = AppState::init(Config::default());
+        for width in [80, 200] {
+            let lines = bottom_row(&app, width, 40);
+            for keybind in keybinds(&app) {
+                assert!(shows(&lines, &keybind), "{} missing at width {}: {:?}", keybind, width, lines);
+            }
+            assert!(shows(&lines, "H|History"));
+        }
+    }
+
+    #[test]
+    fn calendar_shows_how_to_open_a_day() {
+        let mut app = AppState::init(Config::default());
+        app.focused_widget = Widget::Calendar;
+        let lines = bottom_row(&app, 80, 40);
+        assert!(shows(&lines, "Enter|Show day"), "{:?}", lines);
+    }
+
+    #[test]
+    fn projects_show_favorite_and_navigation() {
+        let mut app = AppState::init(Config::default());
+        app.focused_widget = Widget::Projects;
+        let lines = bottom_row(&app, 80, 40);
+        assert!(shows(&lines, "f|Favorite"), "{:?}", lines);
+        assert!(shows(&lines, "Tab|Navigate Projects"), "{:?}", lines);
+    }
+}